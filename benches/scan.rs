@@ -0,0 +1,99 @@
+//! Scan timings over a synthetic vault of 100k cards.
+//!
+//! Run with `cargo bench --bench scan`. Set `CARDDOWN_BENCH_CARDS` to change
+//! the vault size.
+//!
+//! The baseline is the write path scans used before they diffed against the
+//! database: read every row, then delete and reinsert them all. `import`
+//! still writes that way, so it is timed on the same vault.
+
+use std::fs::File;
+use std::path::Path;
+use std::process::Command;
+use std::time::{Duration, Instant, SystemTime};
+use tempfile::TempDir;
+
+const CARDS_PER_FILE: usize = 100;
+
+fn write_file(vault: &Path, file: usize, edit: &str) {
+    let contents: String = (0..CARDS_PER_FILE)
+        .map(|i| format!("Question {file}-{i}{edit}? : Answer {i} 🧠 #bench\n"))
+        .collect();
+    std::fs::write(vault.join(format!("notes_{file:05}.md")), contents).unwrap();
+}
+
+fn scan(vault: &Path, full: bool) -> Duration {
+    let path = vault.to_string_lossy();
+    let mut args = vec!["scan", &*path];
+    if full {
+        args.insert(1, "--full");
+    }
+    carddown(vault, &args)
+}
+
+/// Rewrite every row of the vault's database through `import`, from a copy
+/// whose review counts are all one higher.
+fn rewrite_all(vault: &Path, scratch: &Path) -> Duration {
+    let source = scratch.join("source.db");
+    std::fs::copy(vault.join(".carddown/carddown.db"), &source).unwrap();
+    rusqlite::Connection::open(&source)
+        .unwrap()
+        .execute("UPDATE cards SET revise_count = revise_count + 1", [])
+        .unwrap();
+    carddown(vault, &["import", &source.to_string_lossy()])
+}
+
+fn carddown(vault: &Path, args: &[&str]) -> Duration {
+    let vault = vault.to_string_lossy();
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_carddown"));
+    cmd.env("RUST_LOG", "off").args(["--vault", &vault]);
+    let start = Instant::now();
+    let output = cmd.args(args).output().unwrap();
+    let elapsed = start.elapsed();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    eprintln!("    {}", String::from_utf8_lossy(&output.stderr).trim());
+    elapsed
+}
+
+fn report(name: &str, elapsed: Duration) {
+    println!("{name:<40} {:>8.1} ms", elapsed.as_secs_f64() * 1000.0);
+}
+
+fn main() {
+    let cards: usize = std::env::var("CARDDOWN_BENCH_CARDS")
+        .ok()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(100_000);
+    let files = cards.div_ceil(CARDS_PER_FILE);
+    let vault = TempDir::new().unwrap();
+    for file in 0..files {
+        write_file(vault.path(), file, "");
+    }
+    println!("vault: {files} files, {} cards", files * CARDS_PER_FILE);
+
+    // An incremental scan also fills the scan index
+    report("scan, empty db", scan(vault.path(), false));
+    let scratch = TempDir::new().unwrap();
+    report(
+        "baseline: rewrite every row",
+        rewrite_all(vault.path(), scratch.path()),
+    );
+    report("full scan, nothing changed", scan(vault.path(), true));
+
+    // Edit one file and make sure its mtime moves past the scan index
+    write_file(vault.path(), 0, " (edited)");
+    File::options()
+        .write(true)
+        .open(vault.path().join("notes_00000.md"))
+        .unwrap()
+        .set_modified(SystemTime::now() + Duration::from_secs(5))
+        .unwrap();
+    report("scan, one file edited", scan(vault.path(), false));
+
+    write_file(vault.path(), 1, " (edited)");
+    report("full scan, one file edited", scan(vault.path(), true));
+}
@@ -7,6 +7,7 @@ use ordered_float::OrderedFloat;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use crate::card::Directives;
 use crate::db::GlobalState;
 
 #[derive(Debug, Clone, ValueEnum, Deserialize, PartialEq)]
@@ -29,6 +30,19 @@ pub enum Quality {
 }
 
 impl Quality {
+    /// The quality for a 0-5 grade.
+    pub const fn from_grade(grade: u8) -> Option<Self> {
+        Some(match grade {
+            5 => Self::Perfect,
+            4 => Self::CorrectWithHesitation,
+            3 => Self::CorrectWithDifficulty,
+            2 => Self::IncorrectButEasyToRecall,
+            1 => Self::IncorrectButRemembered,
+            0 => Self::IncorrectAndForgotten,
+            _ => return None,
+        })
+    }
+
     pub const fn failed(&self) -> bool {
         matches!(
             self,
@@ -67,6 +81,20 @@ impl Default for CardState {
 pub trait Algorithm {
     fn update_state(&self, quality: &Quality, state: &mut CardState, global: &mut GlobalState);
     fn name(&self) -> &'static str;
+
+    /// Update `state` after a review, honouring the card's directives.
+    fn review(
+        &self,
+        quality: &Quality,
+        state: &mut CardState,
+        global: &mut GlobalState,
+        directives: &Directives,
+    ) {
+        self.update_state(quality, state, global);
+        if let Some(max_interval) = directives.max_interval {
+            state.interval = state.interval.min(max_interval);
+        }
+    }
 }
 
 pub fn new_algorithm(algo: Algo) -> Box<dyn Algorithm> {
@@ -180,6 +208,24 @@ mod tests {
         assert_eq!(round_float(new_ease_factor(&Quality::Perfect, ef), 2), 2.60);
     }
 
+    #[test]
+    fn test_review_caps_interval() {
+        let directives = Directives {
+            max_interval: Some(3),
+            ..Default::default()
+        };
+        for algo in [Algo::SM2, Algo::SM5, Algo::Simple8] {
+            let algorithm = new_algorithm(algo);
+            let mut state = CardState::default();
+            let mut global = GlobalState::default();
+            for _ in 0..5 {
+                algorithm.review(&Quality::Perfect, &mut state, &mut global, &directives);
+            }
+            assert_eq!(state.interval, 3, "{}", algorithm.name());
+            assert_eq!(state.repetitions, 5);
+        }
+    }
+
     #[test]
     fn test_safe_f64_to_u64() {
         // Normal values
@@ -3,16 +3,350 @@ use anyhow::Result;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
-use std::fs;
 use std::path::Path;
 use std::path::PathBuf;
 use std::sync::LazyLock;
 
-static ONE_LINE_CARD_RE: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"^(.+?)\s*:\s*(.+)").unwrap());
-static TAG_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"#[\w-]+").unwrap());
-static END_OF_CARD_RE: LazyLock<Regex> =
-    LazyLock::new(|| Regex::new(r"^\s*(-\s*-\s*-|\*\s*\*\s*\*)\s*$").unwrap());
+static TAG_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"#[\w-]+(?:/[\w-]+)*").unwrap());
+static CLOZE_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\{\{c(\d+)::(.+?)\}\}").unwrap());
+static MD_HEADING_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^(#{1,6})\s+(.*)$").unwrap());
+static FENCE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*(`{3,}|~{3,})\s*([^`]*?)\s*$").unwrap());
+static CHOICE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*[-*+]\s+\[([ xX])\]\s+(.+?)\s*$").unwrap());
+static HINT_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)^\s*hint:\s*(.+?)\s*$").unwrap());
+/// The `|---|:---:|` row separating a table's header from its data rows.
+static TABLE_DELIMITER_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*\|?\s*:?-+:?\s*(?:\|\s*:?-+:?\s*)*\|?\s*$").unwrap());
+/// A directive tag (`#cd-suspend`) with the whitespace before it.
+static DIRECTIVE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?:^|\s+)#cd-[\w-]+").unwrap());
+static HIGHLIGHT_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"==([^=]+?)==").unwrap());
+/// The tag block ending an org heading, which org only reads as tags at the
+/// very end of the line.
+static ORG_TAGS_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\*+\s.*?(\s+:(?:[\w@#%]+:)+\s*)$").unwrap());
+static ANCHOR_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\s*(?:\^cd-([0-9a-f]{6,})|<!--\s*cd:([0-9a-f]{6,})\s*-->)").unwrap()
+});
+
+/// Most options a multiple-choice card can have, one per number key.
+pub const MAX_CHOICES: usize = 9;
+/// Length of a freshly assigned anchor, extended on collision.
+const ANCHOR_LEN: usize = 6;
+
+pub const FLASHCARD_MARKER: &str = "#flashcard";
+pub const BRAIN_MARKER: &str = "🧠";
+pub const DEFAULT_SEPARATOR: &str = ":";
+/// Tag asking for the answer to be typed in during revise.
+pub const TYPE_TAG: &str = "type";
+/// Prefix of directive tags such as `#cd-suspend`. Not stored as tags.
+const DIRECTIVE_PREFIX: &str = "cd-";
+/// Tag that adds a reverse side to a card. Not stored as a tag.
+pub const REVERSE_TAG: &str = "#reverse";
+const DEFAULT_END_OF_CARD: &str = r"(-\s*-\s*-|\*\s*\*\s*\*)";
+
+/// The line syntax of a vault: the strings that mark a card, the separator
+/// between prompt and response of a one-line card, and the line that ends a
+/// multi-line card.
+#[derive(Debug, Clone)]
+pub struct Syntax {
+    /// Markers for one-line cards. All but 🧠 also start multi-line cards.
+    markers: Vec<String>,
+    separator: String,
+    one_line_re: Regex,
+    end_of_card_re: Regex,
+    /// Add the tags of enclosing markdown headings to each card.
+    inherit_heading_tags: bool,
+    /// Read `#`-prefixed lines as markdown headings. Off for org, where they
+    /// are comments.
+    markdown_headings: bool,
+}
+
+impl Default for Syntax {
+    fn default() -> Self {
+        Self::new(&[], DEFAULT_SEPARATOR, None)
+    }
+}
+
+impl Syntax {
+    pub fn new(extra_markers: &[String], separator: &str, end_of_card: Option<&str>) -> Self {
+        let markers = [FLASHCARD_MARKER, BRAIN_MARKER]
+            .into_iter()
+            .map(str::to_owned)
+            .chain(extra_markers.iter().cloned())
+            .collect();
+        let end_of_card = end_of_card
+            .map(|e| regex::escape(e.trim()))
+            .unwrap_or_else(|| DEFAULT_END_OF_CARD.to_string());
+        Self {
+            markers,
+            separator: separator.to_string(),
+            // A repeated separator only counts with whitespace around it, so
+            // `std::mem::take : ...` still splits at the first `:`
+            one_line_re: Regex::new(&format!(
+                r"^(.+?)(?:\s+((?:{sep}){{2,3}})\s+|\s*{sep}\s*)(.+)",
+                sep = regex::escape(separator)
+            ))
+            .unwrap(),
+            end_of_card_re: Regex::new(&format!(r"^\s*{end_of_card}\s*$")).unwrap(),
+            inherit_heading_tags: false,
+            markdown_headings: true,
+        }
+    }
+
+    pub fn with_heading_tags(mut self, inherit: bool) -> Self {
+        self.inherit_heading_tags = inherit;
+        self
+    }
+
+    pub fn with_markdown_headings(mut self, enabled: bool) -> Self {
+        self.markdown_headings = enabled;
+        self
+    }
+
+    /// Depth and text of `line` if it is a markdown ATX heading (`## Title`).
+    fn heading<'a>(&self, line: &'a str) -> Option<(usize, &'a str)> {
+        let caps = MD_HEADING_RE
+            .captures(line)
+            .filter(|_| self.markdown_headings)?;
+        Some((caps.get(1)?.as_str().len(), caps.get(2)?.as_str()))
+    }
+
+    fn is_card_line(&self, line: &str) -> bool {
+        self.markers.iter().any(|m| line.contains(m.as_str()))
+    }
+
+    fn starts_multi_line(&self, line: &str) -> bool {
+        self.markers
+            .iter()
+            .filter(|m| *m != BRAIN_MARKER)
+            .any(|m| line.contains(m.as_str()))
+    }
+
+    /// A line holding nothing but markers and tags.
+    fn is_bare_marker(&self, line: &str) -> bool {
+        let rest = TAG_RE.replace_all(line, "");
+        let rest = self
+            .markers
+            .iter()
+            .fold(rest.into_owned(), |rest, m| rest.replace(m.as_str(), ""));
+        rest.trim().is_empty()
+    }
+
+    fn parse_tags(&self, line: &str) -> HashSet<String> {
+        TAG_RE
+            .find_iter(line)
+            .map(|m| m.as_str())
+            .filter(|s| !self.markers.iter().any(|m| m == s) && *s != REVERSE_TAG)
+            .filter_map(|s| s.strip_prefix('#'))
+            .filter(|s| !s.is_empty() && !s.starts_with(DIRECTIVE_PREFIX))
+            .map(str::to_owned)
+            .collect()
+    }
+
+    /// Read the directive tags (`#cd-...`) on `line`.
+    fn parse_directives(&self, line: &str) -> Directives {
+        let mut directives = Directives::default();
+        for tag in TAG_RE.find_iter(line) {
+            let Some(directive) = tag.as_str()[1..].strip_prefix(DIRECTIVE_PREFIX) else {
+                continue;
+            };
+            match directive {
+                "suspend" => directives.suspended = true,
+                "priority-low" => directives.priority = Priority::Low,
+                "priority-normal" => directives.priority = Priority::Normal,
+                "priority-high" => directives.priority = Priority::High,
+                _ => match directive
+                    .strip_prefix("max-interval-")
+                    .and_then(|days| days.parse().ok())
+                    .filter(|days| *days >= 1)
+                {
+                    Some(days) => directives.max_interval = Some(days),
+                    None => log::warn!("Ignoring unknown directive {}", tag.as_str()),
+                },
+            }
+        }
+        directives
+    }
+
+    /// Whether `line` carries the `#reverse` tag.
+    fn has_reverse_tag(&self, line: &str) -> bool {
+        TAG_RE.find_iter(line).any(|m| m.as_str() == REVERSE_TAG)
+    }
+
+    /// Remove the directive tags from `line`, wherever they are.
+    fn strip_directives<'a>(&self, line: &'a str) -> std::borrow::Cow<'a, str> {
+        DIRECTIVE_RE.replace_all(line, "")
+    }
+
+    fn strip_tags(&self, line: &str) -> String {
+        let line = self.strip_directives(line);
+        let line = line.as_ref();
+        // Preserve in-text '#' (e.g., "C#", "#1") and only strip from the first
+        // tag/marker segment onward. Prefer the brain marker (and other markers
+        // that aren't tags), then a space+'#', then "#flashcard" as a fallback.
+        let (tag_markers, markers): (Vec<_>, Vec<_>) =
+            self.markers.iter().partition(|m| m.starts_with('#'));
+        let cut_idx = markers
+            .iter()
+            .find_map(|m| line.find(m.as_str()))
+            .or_else(|| line.find(" #"))
+            .or_else(|| tag_markers.iter().find_map(|m| line.find(m.as_str())))
+            .unwrap_or(line.len());
+        line[..cut_idx].trim().to_string()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Default)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CardKind {
+    #[default]
+    Basic,
+    /// One deletion of a cloze sentence. All deletions parsed from the same
+    /// sentence share a `group`; `index` is the cloze number (`c1`, `c2`, ...).
+    Cloze { group: blake3::Hash, index: u32 },
+    /// One side of a card with explicit directions (`::`, `:::` or
+    /// `#reverse`). Prompt and response are already swapped on the reversed
+    /// side.
+    Sided { reversed: bool },
+    /// A multiple-choice card: the response is a `- [ ]` list with one
+    /// `- [x]` item, the option at `answer`.
+    Choice { options: Vec<String>, answer: usize },
+}
+
+/// Read the options of a multiple-choice response: two to [`MAX_CHOICES`]
+/// `- [ ]` items, exactly one of them checked. Items inside code fences
+/// don't count. Indented lines below an item, and code fences they open,
+/// continue that item's option.
+fn parse_choices(response: &[String]) -> Option<CardKind> {
+    let mut options: Vec<String> = vec![];
+    let mut answers = vec![];
+    let mut fence: Option<Fence> = None;
+    let mut in_option = false;
+    // Indentation stripped from the last option's continuation lines
+    let mut width = 0;
+    for line in response {
+        let fenced = fence.is_some();
+        match fence {
+            Some(open) if open.closed_by(line) => fence = None,
+            Some(_) => {}
+            None => fence = Fence::open(line),
+        }
+        let item = CHOICE_RE.captures(line).filter(|_| !fenced);
+        let indent = line.len() - line.trim_start_matches([' ', '\t']).len();
+        if in_option && item.is_none() && (fenced || indent > 0) {
+            if let Some(option) = options.last_mut() {
+                if width == 0 {
+                    width = indent;
+                }
+                option.push('\n');
+                option.push_str(&line[indent.min(width)..]);
+            }
+            continue;
+        }
+        in_option = false;
+        if fenced || fence.is_some() {
+            continue;
+        }
+        if let Some(caps) = item {
+            if &caps[1] != " " {
+                answers.push(options.len());
+            }
+            options.push(caps[2].to_string());
+            in_option = true;
+            width = 0;
+        }
+    }
+    match answers.as_slice() {
+        [answer] if (2..=MAX_CHOICES).contains(&options.len()) => Some(CardKind::Choice {
+            options,
+            answer: *answer,
+        }),
+        _ => None,
+    }
+}
+
+/// How soon a card is asked relative to other due cards.
+#[derive(
+    Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Default, Hash,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+/// Per-card scheduling controls written as `#cd-...` tags in the notes.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Default)]
+#[serde(default)]
+pub struct Directives {
+    /// `#cd-suspend`: never asked during revise.
+    pub suspended: bool,
+    /// `#cd-priority-high` / `#cd-priority-low`: order among due cards.
+    pub priority: Priority,
+    /// `#cd-max-interval-N`: days between reviews never exceed `N`.
+    pub max_interval: Option<u64>,
+}
+
+/// Which sides of a card get asked.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    #[default]
+    Forward,
+    Both,
+    Reverse,
+}
+
+impl Direction {
+    /// Direction of a one-line card from how often the separator is
+    /// repeated: `:` forward, `::` both ways, `:::` reverse only.
+    fn from_separator_count(count: usize) -> Self {
+        match count {
+            2 => Direction::Both,
+            3 => Direction::Reverse,
+            _ => Direction::Forward,
+        }
+    }
+
+    /// `#reverse` adds the reverse side to a forward card.
+    fn with_reverse_tag(self, tagged: bool) -> Self {
+        match self {
+            Direction::Forward if tagged => Direction::Both,
+            other => other,
+        }
+    }
+}
+
+/// Split `card` into the sides asked for by `direction`. The reverse side
+/// gets its own id, derived from the forward one.
+fn sides(card: Card, direction: Direction) -> Vec<Card> {
+    let reverse = |card: &Card| Card {
+        id: blake3::hash(format!("{}\0reverse", card.id).as_bytes()),
+        prompt: card.response.join("\n"),
+        response: vec![card.prompt.clone()],
+        kind: CardKind::Sided { reversed: true },
+        hints: vec![],
+        ..card.clone()
+    };
+    match direction {
+        Direction::Forward => vec![card],
+        Direction::Reverse => vec![reverse(&card)],
+        Direction::Both => {
+            let back = reverse(&card);
+            vec![
+                Card {
+                    kind: CardKind::Sided { reversed: false },
+                    ..card
+                },
+                back,
+            ]
+        }
+    }
+}
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
 pub struct Card {
@@ -22,28 +356,247 @@ pub struct Card {
     pub prompt: String,
     pub response: Vec<String>,
     pub tags: HashSet<String>,
+    #[serde(default)]
+    pub kind: CardKind,
+    /// Titles of the headings enclosing the card, outermost first. Not part
+    /// of the card's identity.
+    #[serde(default)]
+    pub breadcrumb: Vec<String>,
+    /// `hint:` lines of a multi-line card, shown one at a time on request.
+    #[serde(default)]
+    pub hints: Vec<String>,
+    #[serde(default)]
+    pub directives: Directives,
 }
 
-fn parse_tags(line: &str) -> HashSet<String> {
-    TAG_RE
-        .find_iter(line)
-        .map(|m| m.as_str())
-        .filter_map(|s| s.strip_prefix('#'))
-        .filter(|s| !s.is_empty() && *s != "flashcard")
-        .map(str::to_owned)
+/// A single cloze deletion found in a sentence.
+struct Cloze {
+    start: usize,
+    end: usize,
+    index: u32,
+    answer: String,
+}
+
+/// Find the cloze deletions in `text`.
+///
+/// Explicit `{{cN::answer}}` deletions take precedence; when there are none,
+/// each `==highlight==` becomes its own deletion, numbered in order.
+fn find_clozes(text: &str) -> Vec<Cloze> {
+    let clozes: Vec<_> = CLOZE_RE
+        .captures_iter(text)
+        .filter_map(|caps| {
+            let m = caps.get(0)?;
+            Some(Cloze {
+                start: m.start(),
+                end: m.end(),
+                index: caps.get(1)?.as_str().parse().ok()?,
+                answer: caps.get(2)?.as_str().to_string(),
+            })
+        })
+        .collect();
+    if !clozes.is_empty() {
+        return clozes;
+    }
+    HIGHLIGHT_RE
+        .captures_iter(text)
+        .enumerate()
+        .filter_map(|(i, caps)| {
+            let m = caps.get(0)?;
+            Some(Cloze {
+                start: m.start(),
+                end: m.end(),
+                index: i as u32 + 1,
+                answer: caps.get(1)?.as_str().to_string(),
+            })
+        })
         .collect()
 }
 
-fn strip_tags(line: &str) -> String {
-    // Preserve in-text '#' (e.g., "C#", "#1") and only strip from the first
-    // tag/marker segment onward. Prefer the brain marker, then a space+'#',
-    // then "#flashcard" as a fallback.
-    let cut_idx = line
-        .find('🧠')
-        .or_else(|| line.find(" #"))
-        .or_else(|| line.find("#flashcard"))
-        .unwrap_or(line.len());
-    line[..cut_idx].trim().to_string()
+/// Render a cloze sentence with deletion `active` blanked out (or bracketed
+/// when `reveal` is set). All other deletions are shown as plain text.
+pub fn render_cloze(text: &str, active: u32, reveal: bool) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut pos = 0;
+    for cloze in find_clozes(text) {
+        out.push_str(&text[pos..cloze.start]);
+        if cloze.index != active {
+            out.push_str(&cloze.answer);
+        } else if reveal {
+            out.push_str(&format!("[{}]", cloze.answer));
+        } else {
+            out.push_str("[...]");
+        }
+        pos = cloze.end;
+    }
+    out.push_str(&text[pos..]);
+    out
+}
+
+/// Split a written-back identity anchor (`^cd-3fa2b1` or `<!-- cd:3fa2b1 -->`)
+/// off a card line.
+pub fn take_anchor(line: &str) -> (String, Option<String>) {
+    match ANCHOR_RE.captures(line) {
+        Some(caps) => {
+            let anchor = caps.get(1).or_else(|| caps.get(2)).map(|m| m.as_str());
+            (
+                ANCHOR_RE.replace(line, "").into_owned(),
+                anchor.map(str::to_owned),
+            )
+        }
+        None => (line.to_string(), None),
+    }
+}
+
+/// Every identity anchor written in `contents`.
+pub fn anchors(contents: &str) -> Vec<String> {
+    ANCHOR_RE
+        .captures_iter(contents)
+        .filter_map(|caps| caps.get(1).or_else(|| caps.get(2)))
+        .map(|m| m.as_str().to_owned())
+        .collect()
+}
+
+/// The card id for an anchored card. Unlike content hashes this is stable
+/// across edits to the card text.
+pub fn anchor_id(anchor: &str) -> blake3::Hash {
+    blake3::hash(format!("cd:{anchor}").as_bytes())
+}
+
+/// Build one card per cloze index found on `line`, or an empty vec if the
+/// line contains no cloze deletions.
+///
+/// `highlights` allows `==highlight==` deletions; it is off for lines that
+/// already read as another kind of card.
+fn parse_cloze_cards(
+    file: &Path,
+    line_number: u64,
+    line: &str,
+    anchor: Option<&str>,
+    highlights: bool,
+    syntax: &Syntax,
+) -> Vec<Card> {
+    let text = syntax.strip_tags(line);
+    // Highlights on a line that already reads as `prompt : response` are
+    // left alone, so existing cards keep their ids
+    if !CLOZE_RE.is_match(&text) && (!highlights || syntax.one_line_re.is_match(&text)) {
+        return vec![];
+    }
+    let clozes = find_clozes(&text);
+    if clozes.is_empty() {
+        return vec![];
+    }
+    let (group, id_base) = match anchor {
+        Some(anchor) => (anchor_id(anchor), format!("cd:{anchor}")),
+        None => (blake3::hash(text.as_bytes()), text.clone()),
+    };
+    let tags = syntax.parse_tags(line);
+    let directives = syntax.parse_directives(line);
+    let mut indices: Vec<u32> = clozes.iter().map(|c| c.index).collect();
+    indices.sort_unstable();
+    indices.dedup();
+    indices
+        .into_iter()
+        .map(|index| Card {
+            id: blake3::hash(format!("{id_base}\0c{index}").as_bytes()),
+            file: PathBuf::from(file),
+            line: line_number,
+            prompt: text.clone(),
+            response: clozes
+                .iter()
+                .filter(|c| c.index == index)
+                .map(|c| c.answer.clone())
+                .collect(),
+            tags: tags.clone(),
+            kind: CardKind::Cloze { group, index },
+            breadcrumb: vec![],
+            hints: vec![],
+            directives: directives.clone(),
+        })
+        .collect()
+}
+
+/// Split a markdown table row into trimmed cells. `\|` is a literal pipe.
+fn table_cells(row: &str) -> Vec<String> {
+    let row = row.trim();
+    let row = row.strip_prefix('|').unwrap_or(row);
+    let mut cells = vec![String::new()];
+    let mut chars = row.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'|') => {
+                chars.next();
+                cells.last_mut().unwrap().push('|');
+            }
+            '|' => cells.push(String::new()),
+            c => cells.last_mut().unwrap().push(c),
+        }
+    }
+    // Only a closing pipe leaves the last cell entirely empty
+    if cells.len() > 1 && cells.last().is_some_and(String::is_empty) {
+        cells.pop();
+    }
+    cells.into_iter().map(|c| c.trim().to_string()).collect()
+}
+
+/// Build one card per data row of the markdown table starting at
+/// `lines[start]`, introduced by the bare `marker` line. The first column is
+/// the prompt and the others are response lines labelled with their header.
+/// Returns `None` if no table starts there, else the cards and the index of
+/// the first line after the table.
+fn parse_table_cards(
+    file: &Path,
+    lines: &[&str],
+    start: usize,
+    marker: &str,
+    syntax: &Syntax,
+) -> Option<(Vec<Card>, usize)> {
+    let header = lines.get(start).filter(|l| l.contains('|'))?;
+    let delimiter = lines
+        .get(start + 1)
+        .filter(|l| l.contains('|') && TABLE_DELIMITER_RE.is_match(l))?;
+    let header = table_cells(header);
+    if table_cells(delimiter).len() != header.len() {
+        return None;
+    }
+    let tags = syntax.parse_tags(marker);
+    let directives = syntax.parse_directives(marker);
+    let direction = Direction::Forward.with_reverse_tag(syntax.has_reverse_tag(marker));
+    let mut cards = vec![];
+    let mut end = start + 2;
+    while let Some(row) = lines.get(end).filter(|l| l.contains('|')) {
+        let (row, anchor) = take_anchor(row);
+        let cells = table_cells(&row);
+        if let Some(prompt) = cells.first().filter(|p| !p.is_empty()) {
+            let response = header
+                .iter()
+                .zip(&cells)
+                .skip(1)
+                .filter(|(_, cell)| !cell.is_empty())
+                .map(|(name, cell)| match name.is_empty() {
+                    true => cell.clone(),
+                    false => format!("{name}: {cell}"),
+                })
+                .collect();
+            let card = Card {
+                id: anchor
+                    .as_deref()
+                    .map(anchor_id)
+                    .unwrap_or_else(|| blake3::hash(cells.join("\0").as_bytes())),
+                file: PathBuf::from(file),
+                line: end as u64,
+                prompt: prompt.clone(),
+                response,
+                tags: tags.clone(),
+                kind: CardKind::Basic,
+                breadcrumb: vec![],
+                hints: vec![],
+                directives: directives.clone(),
+            };
+            cards.extend(sides(card, direction));
+        }
+        end += 1;
+    }
+    Some((cards, end))
 }
 
 #[derive(Debug, Default)]
@@ -52,26 +605,271 @@ struct ParseState {
     tags: HashSet<String>,
     prompt: Option<String>,
     first_line: Option<u64>,
+    anchor: Option<String>,
+    /// Depth of the markdown heading that opened a heading-scoped card.
+    heading_level: Option<usize>,
+    /// A heading card's first line as read before heading cards existed
+    /// (`## prompt`). Its id is hashed from this when `---` closes it.
+    legacy_prompt: Option<String>,
+    breadcrumb: Vec<String>,
+    direction: Direction,
+    directives: Directives,
 }
 
-pub fn parse_file(file: &Path) -> Result<Vec<Card>> {
-    let contents =
-        fs::read_to_string(file).with_context(|| format!("Error reading `{}`", file.display()))?;
-    if contents.contains("@carddown-ignore") {
-        log::debug!("ignoring file: {}", file.display());
-        return Ok(vec![]);
+impl ParseState {
+    /// Turn the collected lines into a card, one per side.
+    fn finish(mut self, file: &Path) -> Vec<Card> {
+        let (Some(prompt), Some(line)) = (self.prompt, self.first_line) else {
+            return vec![];
+        };
+        if self.heading_level.is_some() {
+            // Sections are usually padded with blank lines
+            while self.card_lines.last().is_some_and(|l| l.trim().is_empty()) {
+                self.card_lines.pop();
+            }
+            let blank = self.card_lines[1..]
+                .iter()
+                .take_while(|l| l.trim().is_empty())
+                .count();
+            self.card_lines.drain(1..1 + blank);
+        }
+        // Hint lines stay in the hash, as they were part of the response
+        // before hints existed
+        let id = self
+            .anchor
+            .as_deref()
+            .map(anchor_id)
+            .unwrap_or_else(|| blake3::hash(self.card_lines.join("\n").as_bytes()));
+        let hints = take_hints(&mut self.card_lines);
+        let response = self.card_lines.into_iter().skip(1).collect::<Vec<_>>();
+        let choices = parse_choices(&response);
+        let card = Card {
+            id,
+            file: PathBuf::from(file),
+            line,
+            prompt,
+            response,
+            tags: self.tags,
+            kind: CardKind::Basic,
+            breadcrumb: self.breadcrumb,
+            hints,
+            directives: self.directives,
+        };
+        match choices {
+            // The options only make sense as answers, so never reverse them
+            Some(kind) => vec![Card { kind, ..card }],
+            None => sides(card, self.direction),
+        }
     }
+}
+
+/// Remove the `hint:` lines outside code fences from a card's lines and
+/// return the hints.
+fn take_hints(card_lines: &mut Vec<String>) -> Vec<String> {
+    let mut hints = vec![];
+    let mut fence: Option<Fence> = None;
+    let mut prompt = true;
+    card_lines.retain(|line| {
+        if std::mem::take(&mut prompt) {
+            return true;
+        }
+        match fence {
+            Some(open) if open.closed_by(line) => fence = None,
+            Some(_) => {}
+            None => fence = Fence::open(line),
+        }
+        match HINT_RE.captures(line).filter(|_| fence.is_none()) {
+            Some(caps) => {
+                hints.push(caps[1].to_string());
+                false
+            }
+            None => true,
+        }
+    });
+    hints
+}
+
+/// An open ``` or ~~~ code fence.
+#[derive(Debug, Clone, Copy)]
+struct Fence {
+    marker: char,
+    len: usize,
+}
+
+impl Fence {
+    fn open(line: &str) -> Option<Self> {
+        let caps = FENCE_RE.captures(line)?;
+        let marker = caps.get(1)?.as_str();
+        Some(Self {
+            marker: marker.chars().next()?,
+            len: marker.len(),
+        })
+    }
+
+    /// A fence is closed by a bare run of at least as many of the same
+    /// character.
+    fn closed_by(&self, line: &str) -> bool {
+        let line = line.trim();
+        line.len() >= self.len && line.chars().all(|c| c == self.marker)
+    }
+}
+
+/// Whether the multi-line card started by a marker line has a body in
+/// `rest`, the lines below it: it is closed by an end-of-card line before the
+/// next card, or for a heading card of `heading_level`, text follows before
+/// the next heading at that level or above.
+fn has_multi_line_body(rest: &[&str], heading_level: Option<usize>, syntax: &Syntax) -> bool {
+    let mut fence: Option<Fence> = None;
+    let mut text = false;
+    for line in rest {
+        if let Some(open) = &fence {
+            if open.closed_by(line) {
+                fence = None;
+            }
+            continue;
+        }
+        if syntax.end_of_card_re.is_match(line) {
+            return true;
+        }
+        if syntax.is_card_line(line) {
+            return false;
+        }
+        if let Some(level) = heading_level {
+            if syntax
+                .heading(line)
+                .is_some_and(|(depth, _)| depth <= level)
+            {
+                return text;
+            }
+        }
+        fence = Fence::open(line);
+        text |= !line.trim().is_empty();
+    }
+    heading_level.is_some() && text
+}
+
+/// Parse one-line (`prompt : response 🧠`), multi-line (`#flashcard` ...
+/// `---`), heading-scoped (`## prompt #flashcard`) and table cards out of
+/// `contents`. This is the syntax shared by all text based parsers.
+pub fn parse_lines(file: &Path, contents: &str, syntax: &Syntax) -> Result<Vec<Card>> {
     let mut cards = vec![];
     let mut state = ParseState::default();
-    for (line_number, line) in contents.lines().enumerate() {
+    // Depth, title and tags of the markdown headings enclosing the current line
+    let mut headings: Vec<(usize, String, HashSet<String>)> = vec![];
+    let mut fence: Option<Fence> = None;
+    let lines: Vec<&str> = contents.lines().collect();
+    // Rows of a table already turned into cards
+    let mut table_end = 0;
+    for (line_number, &line) in lines.iter().enumerate() {
+        if line_number < table_end {
+            continue;
+        }
         log::debug!("line_number: {line_number}, line: {line}");
         log::debug!(
             "first_line: {:?}, card_lines: {:?}",
             state.first_line,
             state.card_lines
         );
-        if line.contains("#flashcard") || line.contains("🧠") {
-            if let Some(caps) = ONE_LINE_CARD_RE.captures(line) {
+        // Nothing inside a code fence is card syntax; fenced blocks within a
+        // card are kept verbatim (including the fence lines).
+        let in_fence = match fence {
+            Some(open) => {
+                if open.closed_by(line) {
+                    fence = None;
+                }
+                true
+            }
+            None => {
+                fence = Fence::open(line);
+                fence.is_some()
+            }
+        };
+        if in_fence {
+            if !state.card_lines.is_empty() {
+                state.card_lines.push(line.to_string());
+            }
+            continue;
+        }
+        // A heading-scoped card runs until the next heading of the same or
+        // higher level, or the next card.
+        if let Some(level) = state.heading_level {
+            let closes = syntax.is_card_line(line)
+                || syntax
+                    .heading(line)
+                    .is_some_and(|(depth, _)| depth <= level);
+            if closes {
+                cards.extend(std::mem::take(&mut state).finish(file));
+            }
+        }
+        let current_heading = syntax.heading(line);
+        if let Some((level, _)) = current_heading {
+            headings.retain(|(depth, _, _)| *depth < level);
+        }
+        let inherited: HashSet<String> = if syntax.inherit_heading_tags {
+            headings
+                .iter()
+                .flat_map(|(_, _, tags)| tags)
+                .cloned()
+                .collect()
+        } else {
+            HashSet::new()
+        };
+        let breadcrumb: Vec<String> = headings
+            .iter()
+            .map(|(_, title, _)| title.clone())
+            .filter(|title| !title.is_empty())
+            .collect();
+        if let Some((level, text)) = current_heading {
+            let title = syntax.strip_tags(&take_anchor(text).0);
+            headings.push((level, title, syntax.parse_tags(text)));
+        }
+        if syntax.is_card_line(line) {
+            let (line, anchor) = take_anchor(line);
+            let line = line.as_str();
+            let heading = syntax
+                .heading(line)
+                .filter(|_| syntax.starts_multi_line(line));
+            // Highlights on the prompt of an existing multi-line card are
+            // left alone, so it keeps its body and id
+            let highlights = !syntax.starts_multi_line(line)
+                || !has_multi_line_body(
+                    &lines[line_number + 1..],
+                    heading.map(|(level, _)| level),
+                    syntax,
+                );
+            let clozes = parse_cloze_cards(
+                file,
+                line_number as u64,
+                line,
+                anchor.as_deref(),
+                highlights,
+                syntax,
+            );
+            if !clozes.is_empty() {
+                cards.extend(clozes.into_iter().map(|mut card| {
+                    card.tags.extend(inherited.iter().cloned());
+                    card.breadcrumb = breadcrumb.clone();
+                    card
+                }));
+                state = ParseState::default();
+            } else if let Some((level, text)) = heading {
+                let prompt = syntax.strip_tags(text);
+                if prompt.is_empty() {
+                    continue;
+                }
+                state = ParseState {
+                    card_lines: vec![prompt.clone()],
+                    tags: &syntax.parse_tags(text) | &inherited,
+                    prompt: Some(prompt),
+                    first_line: Some(line_number as u64),
+                    anchor,
+                    heading_level: Some(level),
+                    legacy_prompt: Some(syntax.strip_tags(line)),
+                    breadcrumb,
+                    direction: Direction::Forward.with_reverse_tag(syntax.has_reverse_tag(text)),
+                    directives: syntax.parse_directives(text),
+                };
+            } else if let Some(caps) = syntax.one_line_re.captures(&syntax.strip_directives(line)) {
                 log::debug!("caps: {caps:?}");
                 let prompt = caps
                     .get(1)
@@ -81,52 +879,142 @@ pub fn parse_file(file: &Path) -> Result<Vec<Card>> {
                 if prompt.is_empty() {
                     continue;
                 }
-                let full_answer = caps.get(2).context("error parsing card answer")?.as_str();
-                let tags = parse_tags(full_answer);
-                cards.push(Card {
-                    id: blake3::hash(strip_tags(line).as_bytes()),
+                let full_answer = caps.get(3).context("error parsing card answer")?.as_str();
+                let direction = Direction::from_separator_count(
+                    caps.get(2)
+                        .map_or(1, |m| m.as_str().matches(syntax.separator.as_str()).count()),
+                )
+                .with_reverse_tag(syntax.has_reverse_tag(full_answer));
+                let tags = &syntax.parse_tags(full_answer) | &inherited;
+                let card = Card {
+                    id: anchor
+                        .as_deref()
+                        .map(anchor_id)
+                        .unwrap_or_else(|| blake3::hash(syntax.strip_tags(line).as_bytes())),
                     file: PathBuf::from(file),
                     line: line_number as u64,
                     prompt: prompt.to_string(),
-                    response: vec![strip_tags(full_answer).to_string()],
+                    response: vec![syntax.strip_tags(full_answer).to_string()],
                     tags,
-                });
+                    kind: CardKind::Basic,
+                    breadcrumb,
+                    hints: vec![],
+                    directives: syntax.parse_directives(line),
+                };
+                cards.extend(sides(card, direction));
                 state = ParseState::default();
-            } else if line.contains("#flashcard") {
-                let prompt = strip_tags(line);
+            } else if syntax.starts_multi_line(line) {
+                // A bare marker line introduces a table of cards
+                let table = syntax
+                    .is_bare_marker(line)
+                    .then(|| parse_table_cards(file, &lines, line_number + 1, line, syntax))
+                    .flatten();
+                if let Some((table, end)) = table {
+                    cards.extend(table.into_iter().map(|mut card| {
+                        card.tags.extend(inherited.iter().cloned());
+                        card.breadcrumb = breadcrumb.clone();
+                        card
+                    }));
+                    table_end = end;
+                    state = ParseState::default();
+                    continue;
+                }
+                let prompt = syntax.strip_tags(line);
                 if prompt.is_empty() {
                     continue;
                 }
-                state.prompt = Some(prompt.clone());
-                state.card_lines.push(prompt);
-                state.first_line = Some(line_number as u64);
-                state.tags = parse_tags(line);
+                state = ParseState {
+                    card_lines: vec![prompt.clone()],
+                    tags: &syntax.parse_tags(line) | &inherited,
+                    prompt: Some(prompt),
+                    first_line: Some(line_number as u64),
+                    anchor,
+                    heading_level: None,
+                    legacy_prompt: None,
+                    breadcrumb,
+                    direction: Direction::Forward.with_reverse_tag(syntax.has_reverse_tag(line)),
+                    directives: syntax.parse_directives(line),
+                };
             }
-        } else if END_OF_CARD_RE.is_match(line) && !state.card_lines.is_empty() {
-            if let (Some(prompt), Some(line)) = (state.prompt.clone(), state.first_line) {
-                let id = blake3::hash(state.card_lines.join("\n").as_bytes());
-                let response = state.card_lines.into_iter().skip(1).collect::<Vec<_>>();
-                cards.push(Card {
-                    id,
-                    file: PathBuf::from(file),
-                    line,
-                    prompt,
-                    response,
-                    tags: state.tags,
-                });
-                state = ParseState::default();
+        } else if syntax.end_of_card_re.is_match(line) && !state.card_lines.is_empty() {
+            // `---` closes a heading card as it did before sections were
+            // cards, so the card keeps its id
+            if let Some(legacy_prompt) = state.legacy_prompt.take() {
+                state.card_lines[0] = legacy_prompt;
+                state.heading_level = None;
             }
+            cards.extend(std::mem::take(&mut state).finish(file));
         } else if !state.card_lines.is_empty() {
             state.card_lines.push(line.to_string());
         }
     }
+    if state.heading_level.is_some() {
+        cards.extend(state.finish(file));
+    }
     Ok(cards)
 }
 
+/// Write an identity anchor onto every line of `contents` holding a card
+/// from `cards` that lacks one.
+///
+/// Anchors are derived from the card's current content hash and extended
+/// until they are unique among `taken`. On org headings the anchor goes
+/// before the tag block. Returns the updated contents, or
+/// `None` if every card already had an anchor.
+pub fn write_anchors(
+    contents: &str,
+    cards: &[Card],
+    taken: &mut HashSet<String>,
+) -> Result<Option<String>> {
+    let mut lines: Vec<String> = contents.lines().map(str::to_owned).collect();
+    let mut anchored = HashSet::new();
+    for card in cards {
+        let idx = card.line as usize;
+        let Some(line) = lines.get(idx) else {
+            continue;
+        };
+        if let (_, Some(existing)) = take_anchor(line) {
+            taken.insert(existing);
+            continue;
+        }
+        if anchored.contains(&idx) {
+            continue;
+        }
+        let hex = card.id.to_hex();
+        let anchor = (ANCHOR_LEN..=hex.len())
+            .map(|len| hex[..len].to_string())
+            .find(|a| !taken.contains(a))
+            .context("error allocating card anchor")?;
+        let line = line.trim_end();
+        let tags_at = ORG_TAGS_RE
+            .captures(line)
+            .and_then(|caps| caps.get(1))
+            .map_or(line.len(), |m| m.start());
+        lines[idx] = format!("{} ^cd-{anchor}{}", &line[..tags_at], &line[tags_at..]);
+        taken.insert(anchor);
+        anchored.insert(idx);
+    }
+    if anchored.is_empty() {
+        return Ok(None);
+    }
+
+    let newline = if contents.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let mut updated = lines.join(newline);
+    if contents.ends_with('\n') {
+        updated.push_str(newline);
+    }
+    Ok(Some(updated))
+}
+
 // add some tests
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::fs;
     use tempfile::{self, NamedTempFile};
 
     fn new_md_file() -> Result<NamedTempFile> {
@@ -136,6 +1024,10 @@ mod tests {
             .context("error creating tempfile")
     }
 
+    fn parse_file(file: &Path) -> Result<Vec<Card>> {
+        parse_lines(file, &fs::read_to_string(file)?, &Syntax::default())
+    }
+
     #[test]
     fn test_parse_multi_line_cards() {
         let file = new_md_file().unwrap();
@@ -213,27 +1105,18 @@ mod tests {
         assert!(cards.is_empty());
     }
 
-    #[test]
-    fn test_parse_file_with_ignore() {
-        let file = new_md_file().unwrap();
-        let data = "@carddown-ignore\nWhat is the answer to life, the universe, and everything?: 42 #flashcard #foo #test";
-        fs::write(file.path(), data).unwrap();
-        let cards = parse_file(file.path()).unwrap();
-        assert!(cards.is_empty());
-    }
-
     #[test]
     fn test_strip_tags() {
         let line =
             "What is the answer to life, the universe, and everything? #flashcard #foo #test";
-        let prompt = strip_tags(line);
+        let prompt = Syntax::default().strip_tags(line);
         assert_eq!(
             prompt,
             "What is the answer to life, the universe, and everything?"
         );
 
         let line = "What is the answer to life, the universe, and everything? 🧠 #foo #test";
-        let prompt = strip_tags(line);
+        let prompt = Syntax::default().strip_tags(line);
         assert_eq!(
             prompt,
             "What is the answer to life, the universe, and everything?"
@@ -242,7 +1125,7 @@ mod tests {
 
     #[test]
     fn test_parse_tags() {
-        let tags = parse_tags("#flashcard #spaced #test # ##");
+        let tags = Syntax::default().parse_tags("#flashcard #spaced #test # ##");
         assert_eq!(
             tags,
             HashSet::from(["spaced".to_string(), "test".to_string()])
@@ -255,7 +1138,7 @@ mod tests {
         // would silently orphan all existing cards in user databases.
         let file = new_md_file().unwrap();
 
-        // Single-line card: hashes strip_tags(line) = "Q1: A1"
+        // Single-line card: hashes Syntax::default().strip_tags(line) = "Q1: A1"
         std::fs::write(file.path(), "Q1: A1 #flashcard\n").unwrap();
         let cards = parse_file(file.path()).unwrap();
         assert_eq!(
@@ -272,6 +1155,165 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_cloze_cards() {
+        let file = new_md_file().unwrap();
+        let data = "The {{c1::mitochondria}} is the {{c2::powerhouse}} of the {{c1::cell}} 🧠 #bio";
+        fs::write(file.path(), data).unwrap();
+        let cards = parse_file(file.path()).unwrap();
+        assert_eq!(cards.len(), 2);
+        let (
+            CardKind::Cloze {
+                group: g1,
+                index: 1,
+            },
+            CardKind::Cloze {
+                group: g2,
+                index: 2,
+            },
+        ) = (&cards[0].kind, &cards[1].kind)
+        else {
+            panic!("expected two cloze cards, got {cards:?}");
+        };
+        assert_eq!(g1, g2);
+        assert_ne!(cards[0].id, cards[1].id);
+        assert_eq!(cards[0].response, vec!["mitochondria", "cell"]);
+        assert_eq!(cards[1].response, vec!["powerhouse"]);
+        assert_eq!(cards[0].tags, HashSet::from(["bio".to_string()]));
+        assert_eq!(
+            cards[0].prompt,
+            "The {{c1::mitochondria}} is the {{c2::powerhouse}} of the {{c1::cell}}"
+        );
+    }
+
+    #[test]
+    fn test_parse_highlight_cloze_cards() {
+        let file = new_md_file().unwrap();
+        let data = "==Paris== is the capital of ==France== #flashcard";
+        fs::write(file.path(), data).unwrap();
+        let cards = parse_file(file.path()).unwrap();
+        assert_eq!(cards.len(), 2);
+        assert_eq!(cards[0].response, vec!["Paris"]);
+        assert_eq!(cards[1].response, vec!["France"]);
+
+        // The one-line separator wins over highlights
+        let data = "Time of day: ==noon== 🧠\na == b : equality 🧠";
+        fs::write(file.path(), data).unwrap();
+        let cards = parse_file(file.path()).unwrap();
+        assert_eq!(cards.len(), 2);
+        assert!(cards.iter().all(|c| c.kind == CardKind::Basic));
+        assert_eq!(cards[1].prompt, "a == b");
+        assert_eq!(cards[1].response, vec!["equality"]);
+        assert_eq!(cards[1].id, blake3::hash(b"a == b : equality"));
+
+        // So does the body of a multi-line card
+        let data = "What is ==this== thing? #flashcard\na body\n---\n\
+                    ## Why ==that==? #flashcard\nBecause\n";
+        fs::write(file.path(), data).unwrap();
+        let cards = parse_file(file.path()).unwrap();
+        assert_eq!(cards.len(), 2);
+        assert!(cards.iter().all(|c| c.kind == CardKind::Basic));
+        assert_eq!(cards[0].prompt, "What is ==this== thing?");
+        assert_eq!(cards[0].response, vec!["a body"]);
+        assert_eq!(
+            cards[0].id,
+            blake3::hash(b"What is ==this== thing?\na body")
+        );
+        assert_eq!(cards[1].response, vec!["Because"]);
+    }
+
+    #[test]
+    fn test_render_cloze() {
+        let text = "The {{c1::mitochondria}} is the {{c2::powerhouse}} of the cell";
+        assert_eq!(
+            render_cloze(text, 1, false),
+            "The [...] is the powerhouse of the cell"
+        );
+        assert_eq!(
+            render_cloze(text, 2, true),
+            "The mitochondria is the [powerhouse] of the cell"
+        );
+        assert_eq!(render_cloze("==a== and ==b==", 2, false), "a and [...]");
+    }
+
+    #[test]
+    fn test_cloze_id_stability() {
+        let file = new_md_file().unwrap();
+        std::fs::write(file.path(), "A {{c1::B}} 🧠\n").unwrap();
+        let cards = parse_file(file.path()).unwrap();
+        assert_eq!(
+            cards[0].id.to_hex().as_str(),
+            blake3::hash(b"A {{c1::B}}\0c1").to_hex().as_str()
+        );
+        assert_eq!(
+            cards[0].kind,
+            CardKind::Cloze {
+                group: blake3::hash(b"A {{c1::B}}"),
+                index: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_anchored_cards() {
+        let file = new_md_file().unwrap();
+
+        // The anchor is the identity, so edits keep the id
+        fs::write(file.path(), "Q1: A1 🧠 #tag ^cd-3fa2b1\n").unwrap();
+        let cards = parse_file(file.path()).unwrap();
+        assert_eq!(cards[0].id, anchor_id("3fa2b1"));
+        assert_eq!(cards[0].response, vec!["A1"]);
+        assert_eq!(cards[0].tags, HashSet::from(["tag".to_string()]));
+        fs::write(file.path(), "Q1: A1 fixed 🧠 #tag ^cd-3fa2b1\n").unwrap();
+        assert_eq!(parse_file(file.path()).unwrap()[0].id, anchor_id("3fa2b1"));
+
+        // HTML comment form on a multi-line card
+        fs::write(file.path(), "Q1 #flashcard <!-- cd:3fa2b1 -->\nA1\n---\n").unwrap();
+        let cards = parse_file(file.path()).unwrap();
+        assert_eq!(cards[0].id, anchor_id("3fa2b1"));
+        assert_eq!(cards[0].prompt, "Q1");
+    }
+
+    #[test]
+    fn test_write_anchors() {
+        let file = Path::new("notes.md");
+        let data =
+            "Q1: A1 🧠\nQ2 #flashcard\nA2\n---\nThe {{c1::a}} {{c2::b}} 🧠\nQ3: A3 🧠 ^cd-aaaaaa\n";
+        let cards = parse_lines(file, data, &Syntax::default()).unwrap();
+
+        let mut taken = HashSet::new();
+        let updated = write_anchors(data, &cards, &mut taken).unwrap().unwrap();
+        assert_eq!(taken.len(), 4);
+        let first = cards[0].id.to_hex();
+        assert!(updated.starts_with(&format!("Q1: A1 🧠 ^cd-{}\n", &first[..ANCHOR_LEN])));
+        assert!(updated.ends_with("Q3: A3 🧠 ^cd-aaaaaa\n"));
+
+        let after = parse_lines(file, &updated, &Syntax::default()).unwrap();
+        assert_eq!(cards.len(), after.len());
+        for (before, after) in cards.iter().zip(after.iter()) {
+            assert_eq!(before.prompt, after.prompt);
+            assert_eq!(before.response, after.response);
+        }
+
+        // Running again is a no-op
+        assert!(write_anchors(&updated, &after, &mut taken)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_write_anchors_avoids_collisions() {
+        let data = "Q1: A1 🧠\r\n";
+        let cards = parse_lines(Path::new("notes.md"), data, &Syntax::default()).unwrap();
+        let hex = cards[0].id.to_hex();
+        let mut taken = HashSet::from([hex[..ANCHOR_LEN].to_string()]);
+        let updated = write_anchors(data, &cards, &mut taken).unwrap().unwrap();
+        assert_eq!(
+            updated,
+            format!("Q1: A1 🧠 ^cd-{}\r\n", &hex[..ANCHOR_LEN + 1])
+        );
+    }
+
     #[test]
     fn test_serde() -> Result<()> {
         let card = Card {
@@ -281,6 +1323,14 @@ mod tests {
             tags: HashSet::from(["test".to_string()]),
             prompt: "What is the answer to life, the universe, and everything?".to_string(),
             response: vec!["42".to_string()],
+            kind: CardKind::Basic,
+            breadcrumb: vec!["Rust".to_string()],
+            hints: vec!["Starts with 4".to_string()],
+            directives: Directives {
+                suspended: true,
+                priority: Priority::High,
+                max_interval: Some(30),
+            },
         };
         let data = serde_json::to_string(&card)?;
         let card2: Card = serde_json::from_str(&data)?;
@@ -336,7 +1386,7 @@ mod tests {
         let cards = parse_file(file.path()).unwrap();
         assert!(cards.is_empty());
 
-        // Test malformed one-line card (should be empty because it doesn't match ONE_LINE_CARD_RE)
+        // Test malformed one-line card (should be empty because it doesn't match the one-line separator)
         let data = ": answer #flashcard";
         fs::write(file.path(), data).unwrap();
         let cards = parse_file(file.path()).unwrap();
@@ -428,9 +1478,510 @@ Q4: A4 #flashcard";
         }
     }
 
+    #[test]
+    fn test_sided_cards() {
+        let data = "hola : hello 🧠\n\
+                    perro :: dog 🧠 #spanish\n\
+                    gato ::: cat 🧠\n\
+                    casa : house 🧠 #reverse\n\
+                    The {{c1::sun}} rises 🧠 #reverse\n\
+                    Define mutex #flashcard #reverse\n\
+                    Mutual exclusion lock\n\
+                    ---\n";
+        let cards = parse_lines(Path::new("vocab.md"), data, &Syntax::default()).unwrap();
+        let summary: Vec<_> = cards
+            .iter()
+            .map(|c| (c.prompt.as_str(), c.response.join("\n"), c.kind.clone()))
+            .collect();
+        let fwd = CardKind::Sided { reversed: false };
+        let back = CardKind::Sided { reversed: true };
+        assert_eq!(summary.len(), 9);
+        assert_eq!(summary[0], ("hola", "hello".into(), CardKind::Basic));
+        assert_eq!(summary[1], ("perro", "dog".into(), fwd.clone()));
+        assert_eq!(summary[2], ("dog", "perro".into(), back.clone()));
+        assert_eq!(summary[3], ("cat", "gato".into(), back.clone()));
+        assert_eq!(summary[4], ("casa", "house".into(), fwd.clone()));
+        assert_eq!(summary[5], ("house", "casa".into(), back.clone()));
+        assert!(matches!(summary[6].2, CardKind::Cloze { .. }));
+        assert_eq!(
+            summary[7],
+            ("Define mutex", "Mutual exclusion lock".into(), fwd)
+        );
+        assert_eq!(
+            summary[8],
+            ("Mutual exclusion lock", "Define mutex".into(), back)
+        );
+
+        // Each side has its own id; #reverse is not a tag and doesn't change
+        // the forward id
+        assert_ne!(cards[1].id, cards[2].id);
+        assert_eq!(cards[2].tags, HashSet::from(["spanish".to_string()]));
+        assert!(cards[4].tags.is_empty());
+        let plain = parse_lines(Path::new("vocab.md"), "casa : house 🧠", &Syntax::default());
+        assert_eq!(plain.unwrap()[0].id, cards[4].id);
+    }
+
+    #[test]
+    fn test_repeated_separator_needs_whitespace() {
+        let cards = parse_lines(
+            Path::new("notes.md"),
+            "a::b : c 🧠\nstd::mem::take : moves out 🧠\n",
+            &Syntax::default(),
+        )
+        .unwrap();
+        assert_eq!(cards.len(), 2);
+        assert!(cards.iter().all(|c| c.kind == CardKind::Basic));
+        // Split at the first separator, as before double-sided cards
+        assert_eq!(cards[0].prompt, "a");
+        assert_eq!(cards[0].response, vec![":b : c"]);
+        assert_eq!(cards[0].id, blake3::hash(b"a::b : c"));
+    }
+
+    #[test]
+    fn test_sided_cards_custom_separator() {
+        let syntax = Syntax::new(&[], "=>", None);
+        let data = "a => b 🧠\nc =>=> d 🧠\ne =>=>=> f 🧠\n";
+        let cards = parse_lines(Path::new("vocab.md"), data, &syntax).unwrap();
+        let kinds: Vec<_> = cards.iter().map(|c| c.kind.clone()).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                CardKind::Basic,
+                CardKind::Sided { reversed: false },
+                CardKind::Sided { reversed: true },
+                CardKind::Sided { reversed: true },
+            ]
+        );
+        assert_eq!(cards[3].prompt, "f");
+    }
+
+    #[test]
+    fn test_multiple_choice_cards() {
+        let data = "Capital of France? #flashcard #reverse\n\
+                    - [ ] Rome\n\
+                    - [x] Paris\n\
+                    * [ ] `Oslo`\n\
+                    Paris sits on the Seine.\n\
+                    ---\n\
+                    Two answers #flashcard\n\
+                    - [x] a\n\
+                    - [X] b\n\
+                    ---\n\
+                    Fenced #flashcard\n\
+                    - [x] a\n\
+                    ```\n\
+                    - [ ] not an option\n\
+                    ```\n\
+                    ---\n\
+                    ## Largest planet? #flashcard\n\
+                    - [ ] Mars\n\
+                    - [x] Jupiter\n\
+                    # Which prints? #flashcard\n\
+                    - [x] This one\n\
+                    \x20 ```rust\n\
+                    \x20 fn main() {\n\
+                    \x20     println!(\"hi\");\n\
+                    \x20 }\n\
+                    \x20 ```\n\
+                    - [ ] Two lines\n\
+                    \x20 of text\n\
+                    Not part of an option\n";
+        let cards = parse_lines(Path::new("quiz.md"), data, &Syntax::default()).unwrap();
+        assert_eq!(cards.len(), 5);
+        assert_eq!(
+            cards[0].kind,
+            CardKind::Choice {
+                options: vec!["Rome".into(), "Paris".into(), "`Oslo`".into()],
+                answer: 1
+            }
+        );
+        assert_eq!(cards[0].response.len(), 4);
+        assert_eq!(cards[1].kind, CardKind::Basic);
+        assert_eq!(cards[2].kind, CardKind::Basic);
+        assert_eq!(
+            cards[3].kind,
+            CardKind::Choice {
+                options: vec!["Mars".into(), "Jupiter".into()],
+                answer: 1
+            }
+        );
+        assert_eq!(
+            cards[4].kind,
+            CardKind::Choice {
+                options: vec![
+                    "This one\n```rust\nfn main() {\n    println!(\"hi\");\n}\n```".into(),
+                    "Two lines\nof text".into()
+                ],
+                answer: 0
+            }
+        );
+    }
+
+    #[test]
+    fn test_hints() {
+        let data = "Capital of France? #flashcard\n\
+                    Paris\n\
+                    hint: On the Seine\n\
+                    Hint:   Starts with P  \n\
+                    ```\n\
+                    hint: not a hint\n\
+                    ```\n\
+                    ---\n\
+                    Capital of France? #flashcard\n\
+                    Paris\n\
+                    ```\n\
+                    hint: not a hint\n\
+                    ```\n\
+                    ---\n\
+                    ## hint: the prompt #flashcard\n\
+                    answer\n";
+        let cards = parse_lines(Path::new("notes.md"), data, &Syntax::default()).unwrap();
+        assert_eq!(cards.len(), 3);
+        assert_eq!(cards[0].hints, vec!["On the Seine", "Starts with P"]);
+        assert_eq!(
+            cards[0].response,
+            vec!["Paris", "```", "hint: not a hint", "```"]
+        );
+        // Hint lines are hashed like any other line, so existing cards keep
+        // their ids
+        assert_eq!(
+            cards[0].id,
+            blake3::hash(
+                b"Capital of France?\nParis\nhint: On the Seine\nHint:   Starts with P  \n\
+                  ```\nhint: not a hint\n```"
+            )
+        );
+        assert_ne!(cards[0].id, cards[1].id);
+        assert!(cards[1].hints.is_empty());
+        assert_eq!(cards[2].prompt, "hint: the prompt");
+        assert!(cards[2].hints.is_empty());
+    }
+
+    #[test]
+    fn test_table_cards() {
+        let data = "# Geography\n\
+                    #flashcard #geo\n\
+                    | Country | Capital | River |\n\
+                    |:--------|:-------:|------:|\n\
+                    | France  | Paris   | Seine |\n\
+                    | Egypt   | Cairo   |       |\n\
+                    |         | orphan  |       |\n\
+                    | Pipes   | a \\| b  | Nile | ^cd-abcdef\n\
+                    \n\
+                    Compare #flashcard\n\
+                    | a | b |\n\
+                    |---|---|\n\
+                    ---\n";
+        let cards = parse_lines(Path::new("notes.md"), data, &Syntax::default()).unwrap();
+        assert_eq!(cards.len(), 4);
+        assert_eq!(cards[0].prompt, "France");
+        assert_eq!(cards[0].response, vec!["Capital: Paris", "River: Seine"]);
+        assert_eq!(cards[0].line, 4);
+        assert_eq!(cards[0].tags, HashSet::from(["geo".to_string()]));
+        assert_eq!(cards[0].breadcrumb, vec!["Geography"]);
+        assert_eq!(cards[1].response, vec!["Capital: Cairo"]);
+        assert_eq!(cards[2].response, vec!["Capital: a | b", "River: Nile"]);
+        assert_eq!(cards[2].id, anchor_id("abcdef"));
+        // A marker line with a prompt keeps the table as its response
+        assert_eq!(cards[3].prompt, "Compare");
+        assert_eq!(cards[3].response, vec!["| a | b |", "|---|---|"]);
+
+        // A row's id depends only on its own content
+        let moved = "#flashcard\n| Country | Capital | River |\n|---|---|---|\n\
+                     | Spain | Madrid | Tagus |\n| France | Paris | Seine |\n";
+        let other = parse_lines(Path::new("notes.md"), moved, &Syntax::default()).unwrap();
+        assert_eq!(other[1].id, cards[0].id);
+        assert_ne!(other[0].id, cards[0].id);
+    }
+
+    #[test]
+    fn test_table_cells() {
+        assert_eq!(table_cells("| a | b |"), vec!["a", "b"]);
+        assert_eq!(table_cells("a | b"), vec!["a", "b"]);
+        assert_eq!(table_cells("| a | |"), vec!["a", ""]);
+        assert_eq!(table_cells(r"| a \| b | c \|"), vec!["a | b", "c |"]);
+        assert!(!TABLE_DELIMITER_RE.is_match("| a | b |"));
+        assert!(TABLE_DELIMITER_RE.is_match("|:--|--:|:-:|"));
+    }
+
+    #[test]
+    fn test_directives() {
+        let data = "Q1 : A1 🧠 #rust #cd-suspend\n\
+                    Q2 : A2 🧠 #cd-priority-high #cd-max-interval-30 #cd-bogus\n\
+                    The {{c1::sun}} rises 🧠 #cd-priority-low #cd-max-interval-0\n\
+                    Q3 #flashcard #cd-max-interval-x #cd-suspend\n\
+                    A3\n\
+                    ---\n";
+        let cards = parse_lines(Path::new("notes.md"), data, &Syntax::default()).unwrap();
+        assert_eq!(cards.len(), 4);
+        assert!(cards[0].directives.suspended);
+        assert_eq!(cards[0].tags, HashSet::from(["rust".to_string()]));
+        assert_eq!(
+            cards[1].directives,
+            Directives {
+                suspended: false,
+                priority: Priority::High,
+                max_interval: Some(30),
+            }
+        );
+        assert!(cards[1].tags.is_empty());
+        assert_eq!(cards[2].directives.priority, Priority::Low);
+        assert_eq!(cards[2].directives.max_interval, None);
+        assert!(cards[3].directives.suspended);
+        assert_eq!(cards[3].directives.max_interval, None);
+        assert!(cards
+            .iter()
+            .all(|c| c.tags.iter().all(|t| !t.starts_with("cd-"))));
+
+        // Directives are not part of the id
+        let plain = "Q1 : A1 🧠\nQ3 #flashcard\nA3\n---\n";
+        let plain = parse_lines(Path::new("notes.md"), plain, &Syntax::default()).unwrap();
+        assert_eq!(plain[0].id, cards[0].id);
+        assert_eq!(plain[1].id, cards[3].id);
+    }
+
+    #[test]
+    fn test_directives_before_marker() {
+        let data = "Q : Y #cd-suspend 🧠\n\
+                    Q #cd-priority-high : Y 🧠\n\
+                    Q : Y 🧠 #cd-max-interval-7\n";
+        let cards = parse_lines(Path::new("notes.md"), data, &Syntax::default()).unwrap();
+        assert_eq!(cards.len(), 3);
+        for card in &cards {
+            assert_eq!(card.prompt, "Q");
+            assert_eq!(card.response, vec!["Y"]);
+            assert_eq!(card.id, blake3::hash(b"Q : Y"));
+        }
+        assert!(cards[0].directives.suspended);
+        assert_eq!(cards[1].directives.priority, Priority::High);
+        assert_eq!(cards[2].directives.max_interval, Some(7));
+    }
+
+    #[test]
+    fn test_directives_on_reverse_sides() {
+        let data = "Hund :: dog 🧠 #cd-suspend #cd-max-interval-3\n\
+                    Katze ::: cat 🧠 #cd-priority-high\n";
+        let cards = parse_lines(Path::new("vocab.md"), data, &Syntax::default()).unwrap();
+        assert_eq!(cards.len(), 3);
+        let capped = Directives {
+            suspended: true,
+            priority: Priority::Normal,
+            max_interval: Some(3),
+        };
+        assert_eq!(cards[0].directives, capped);
+        assert_eq!(cards[1].prompt, "dog");
+        assert_eq!(cards[1].directives, capped);
+        assert_eq!(cards[2].prompt, "cat");
+        assert_eq!(cards[2].directives.priority, Priority::High);
+    }
+
+    #[test]
+    fn test_parse_heading_cards() {
+        let data = "# Rust\n\
+                    ## What is ownership? #flashcard #rust\n\
+                    \n\
+                    Each value has a single owner.\n\
+                    ### Borrowing\n\
+                    References don't own.\n\
+                    \n\
+                    ## Lifetimes\n\
+                    Not a card\n\
+                    ---\n\
+                    ### What is 'static? #flashcard\n\
+                    Lives for the whole program.\n\
+                    Capital of France? : Paris 🧠\n\
+                    # Python #flashcard\n\
+                    Runs to the end of the file\n";
+        let cards = parse_lines(Path::new("notes.md"), data, &Syntax::default()).unwrap();
+        assert_eq!(cards.len(), 4);
+        assert_eq!(cards[0].prompt, "What is ownership?");
+        assert_eq!(cards[0].line, 1);
+        assert_eq!(
+            cards[0].response,
+            vec![
+                "Each value has a single owner.",
+                "### Borrowing",
+                "References don't own."
+            ]
+        );
+        assert_eq!(cards[0].tags, HashSet::from(["rust".to_string()]));
+        assert_eq!(cards[1].prompt, "What is 'static?");
+        assert_eq!(cards[1].response, vec!["Lives for the whole program."]);
+        assert_eq!(cards[2].prompt, "Capital of France?");
+        assert_eq!(cards[3].prompt, "Python");
+        assert_eq!(cards[3].response, vec!["Runs to the end of the file"]);
+    }
+
+    #[test]
+    fn test_inherit_heading_tags() {
+        let data = "# Rust #lang\n\
+                    ## Ownership #memory\n\
+                    Who frees it? : The owner 🧠 #own\n\
+                    ### Moves #flashcard #moves\n\
+                    A move transfers ownership\n\
+                    ## Traits\n\
+                    What is Send? : Safe to move across threads 🧠\n\
+                    # Python\n\
+                    The {{c1::GIL}} serialises bytecode 🧠\n";
+        let file = Path::new("notes.md");
+        let tags = |cards: &[Card], i: usize| {
+            let mut tags: Vec<_> = cards[i].tags.iter().cloned().collect();
+            tags.sort();
+            tags
+        };
+
+        let cards = parse_lines(file, data, &Syntax::default()).unwrap();
+        assert_eq!(tags(&cards, 0), vec!["own"]);
+        assert_eq!(tags(&cards, 1), vec!["moves"]);
+        assert!(cards[2].tags.is_empty());
+
+        let syntax = Syntax::default().with_heading_tags(true);
+        let cards = parse_lines(file, data, &syntax).unwrap();
+        assert_eq!(cards.len(), 4);
+        assert_eq!(tags(&cards, 0), vec!["lang", "memory", "own"]);
+        assert_eq!(tags(&cards, 1), vec!["lang", "memory", "moves"]);
+        assert_eq!(tags(&cards, 2), vec!["lang"]);
+        assert!(cards[3].tags.is_empty());
+    }
+
+    #[test]
+    fn test_breadcrumb() {
+        let data = "# Rust #lang\n\
+                    ## Iterators\n\
+                    ### Option #flashcard\n\
+                    Implements IntoIterator\n\
+                    ### Map\n\
+                    Is it lazy? : Yes 🧠\n\
+                    # Python\n\
+                    The {{c1::GIL}} serialises bytecode 🧠\n\
+                    What is PEP 8? #flashcard\n\
+                    A style guide\n\
+                    ---\n";
+        let cards = parse_lines(Path::new("notes.md"), data, &Syntax::default()).unwrap();
+        let crumbs: Vec<_> = cards.iter().map(|c| c.breadcrumb.join(" > ")).collect();
+        assert_eq!(
+            crumbs,
+            vec![
+                "Rust > Iterators",
+                "Rust > Iterators > Map",
+                "Python",
+                "Python"
+            ]
+        );
+
+        // Moving a card to another section keeps its id
+        let moved = "# Elsewhere\nIs it lazy? : Yes 🧠\n";
+        let other = parse_lines(Path::new("notes.md"), moved, &Syntax::default()).unwrap();
+        assert_eq!(other[0].id, cards[1].id);
+        assert_eq!(other[0].breadcrumb, vec!["Elsewhere"]);
+    }
+
+    #[test]
+    fn test_heading_card_closed_by_rule_keeps_id() {
+        // Written before heading cards, when `---` ended every block
+        let data = "## What is ownership? #flashcard\n\nEach value has one owner.\n---\n## Next\n";
+        let cards = parse_lines(Path::new("notes.md"), data, &Syntax::default()).unwrap();
+        assert_eq!(cards.len(), 1);
+        assert_eq!(
+            cards[0].id,
+            blake3::hash(b"## What is ownership?\n\nEach value has one owner.")
+        );
+        assert_eq!(cards[0].prompt, "What is ownership?");
+    }
+
+    #[test]
+    fn test_heading_card_anchor() {
+        let before = "## Q #flashcard ^cd-abcdef\nA\n";
+        let after = "## Q edited #flashcard ^cd-abcdef\nA\n";
+        let before = parse_lines(Path::new("notes.md"), before, &Syntax::default()).unwrap();
+        let after = parse_lines(Path::new("notes.md"), after, &Syntax::default()).unwrap();
+        assert_eq!(before[0].id, after[0].id);
+        assert_eq!(after[0].prompt, "Q edited");
+    }
+
+    #[test]
+    fn test_code_fences() {
+        let data = "Explain this snippet #flashcard #code\n\
+                    ```rust\n\
+                    // C# style #flashcard comment : nope 🧠\n\
+                    let x = 1;\n\
+                    ---\n\
+                    ```\n\
+                    Binds x\n\
+                    ---\n\
+                    ~~~~sh\n\
+                    # flashcard\n\
+                    echo hi : there 🧠\n\
+                    ~~~\n\
+                    ~~~~\n\
+                    ## Shell #flashcard\n\
+                    ```bash\n\
+                    # not a heading\n\
+                    ```\n\
+                    After : fence 🧠\n";
+        let cards = parse_lines(Path::new("notes.md"), data, &Syntax::default()).unwrap();
+        assert_eq!(cards.len(), 3);
+        assert_eq!(cards[0].prompt, "Explain this snippet");
+        assert_eq!(
+            cards[0].response,
+            vec![
+                "```rust",
+                "// C# style #flashcard comment : nope 🧠",
+                "let x = 1;",
+                "---",
+                "```",
+                "Binds x"
+            ]
+        );
+        assert_eq!(cards[1].prompt, "Shell");
+        assert_eq!(cards[1].response, vec!["```bash", "# not a heading", "```"]);
+        assert_eq!(cards[2].prompt, "After");
+    }
+
+    #[test]
+    fn test_unclosed_fence_runs_to_end() {
+        let data = "```\nQ : A 🧠\n";
+        let cards = parse_lines(Path::new("notes.md"), data, &Syntax::default()).unwrap();
+        assert!(cards.is_empty());
+    }
+
+    #[test]
+    fn test_custom_syntax() {
+        let syntax = Syntax::new(&["❓".to_string(), "#card".to_string()], "::", Some("%%"));
+        let data = "Meeting at 10:30 : noon ❓\n\
+                    See https://example.com :: a link ❓ #web\n\
+                    Multi #card\n\
+                    line\n\
+                    ---\n\
+                    more\n\
+                    %%\n\
+                    Old : style 🧠\n";
+        let cards = parse_lines(Path::new("notes.md"), data, &syntax).unwrap();
+        assert_eq!(cards.len(), 2);
+        assert_eq!(cards[0].prompt, "See https://example.com");
+        assert_eq!(cards[0].response, vec!["a link"]);
+        assert_eq!(cards[0].tags, HashSet::from(["web".to_string()]));
+        assert_eq!(cards[1].prompt, "Multi");
+        assert_eq!(cards[1].response, vec!["line", "---", "more"]);
+        assert!(cards[1].tags.is_empty());
+    }
+
+    #[test]
+    fn test_parse_hierarchical_tags() {
+        let tags = Syntax::default().parse_tags("#lang/rust/async #topic/ #a//b #flashcard");
+        assert_eq!(
+            tags,
+            HashSet::from([
+                "lang/rust/async".to_string(),
+                "topic".to_string(),
+                "a".to_string()
+            ])
+        );
+    }
+
     #[test]
     fn test_parse_tags_with_hyphens() {
-        let tags = parse_tags("#flashcard #tag-with-hyphen #another-tag");
+        let tags = Syntax::default().parse_tags("#flashcard #tag-with-hyphen #another-tag");
         assert_eq!(
             tags,
             HashSet::from(["tag-with-hyphen".to_string(), "another-tag".to_string()])
@@ -440,7 +1991,7 @@ Q4: A4 #flashcard";
     #[test]
     fn test_strip_tags_whitespace() {
         let line = "  What is the answer?  #flashcard";
-        let prompt = strip_tags(line);
+        let prompt = Syntax::default().strip_tags(line);
         assert_eq!(prompt, "What is the answer?");
     }
 
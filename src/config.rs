@@ -1,6 +1,9 @@
 use crate::algorithm::Algo;
+use crate::card::{self, Syntax};
+use crate::parser::ParserKind;
 use crate::LeechMethod;
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
 #[derive(Debug, Default, Deserialize, Clone, PartialEq)]
@@ -21,6 +24,52 @@ pub struct StorageConfig {
 #[serde(default, deny_unknown_fields)]
 pub struct ScanConfig {
     pub file_types: Option<Vec<String>>,
+    /// Extra file extensions mapped onto a built-in parser, e.g. `mdx = "markdown"`
+    pub parsers: HashMap<String, ParserKind>,
+    /// Extra card markers, in addition to `#flashcard` and 🧠
+    pub markers: Option<Vec<String>>,
+    /// Separator between prompt and response of a one-line card. Default: `:`
+    pub separator: Option<String>,
+    /// Line ending a multi-line card. Default: `---` or `***`
+    pub end_of_card: Option<String>,
+    /// Add the tags of enclosing markdown headings to each card
+    pub inherit_heading_tags: Option<bool>,
+}
+
+impl ScanConfig {
+    /// Validate the card syntax settings and build the parser syntax from them.
+    pub fn syntax(&self) -> Result<Syntax, String> {
+        let markers = self.markers.clone().unwrap_or_default();
+        if markers.iter().any(|m| m.trim().is_empty()) {
+            return Err("scan.markers must not contain empty markers".to_string());
+        }
+        if let Some(m) = markers.iter().find(|m| m.chars().any(char::is_whitespace)) {
+            return Err(format!("scan.markers: `{m}` must not contain whitespace"));
+        }
+        let separator = self.separator.as_deref().unwrap_or(card::DEFAULT_SEPARATOR);
+        if separator.trim().is_empty() {
+            return Err("scan.separator must not be empty".to_string());
+        }
+        if separator.contains('#') || markers.iter().any(|m| m.contains(separator)) {
+            return Err(format!(
+                "scan.separator `{separator}` must not overlap with tags or markers"
+            ));
+        }
+        if let Some(end) = &self.end_of_card {
+            if end.trim().is_empty() {
+                return Err("scan.end_of_card must not be empty".to_string());
+            }
+            if end.contains(separator) {
+                return Err(format!(
+                    "scan.end_of_card `{end}` must not contain the separator `{separator}`"
+                ));
+            }
+        }
+        Ok(
+            Syntax::new(&markers, separator, self.end_of_card.as_deref())
+                .with_heading_tags(self.inherit_heading_tags.unwrap_or(false)),
+        )
+    }
 }
 
 #[derive(Debug, Default, Deserialize, Clone, PartialEq)]
@@ -32,6 +81,8 @@ pub struct ReviseConfig {
     pub leech_method: Option<LeechMethod>,
     pub algorithm: Option<Algo>,
     pub reverse_probability: Option<f64>,
+    pub type_answers: Option<bool>,
+    pub letter_hints: Option<bool>,
 }
 
 pub fn project_config_path(vault_dir: &Path) -> PathBuf {
@@ -63,6 +114,14 @@ mod tests {
             [scan]
             file_types = ["md", "txt"]
 
+            markers = ["❓"]
+            separator = "::"
+            end_of_card = "%%"
+            inherit_heading_tags = true
+
+            [scan.parsers]
+            mdx = "markdown"
+
             [revise]
             maximum_cards_per_session = 10
             maximum_duration_of_session = 15
@@ -82,6 +141,15 @@ mod tests {
             config.scan.file_types,
             Some(vec!["md".to_string(), "txt".to_string()])
         );
+        assert_eq!(
+            config.scan.parsers,
+            HashMap::from([("mdx".to_string(), ParserKind::Markdown)])
+        );
+        assert_eq!(config.scan.markers, Some(vec!["❓".to_string()]));
+        assert_eq!(config.scan.separator, Some("::".to_string()));
+        assert_eq!(config.scan.end_of_card, Some("%%".to_string()));
+        assert_eq!(config.scan.inherit_heading_tags, Some(true));
+        assert!(config.scan.syntax().is_ok());
         assert_eq!(config.revise.maximum_cards_per_session, Some(10));
         assert_eq!(config.revise.maximum_duration_of_session, Some(15));
         assert_eq!(config.revise.leech_failure_threshold, Some(7));
@@ -102,4 +170,47 @@ mod tests {
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("nope"));
     }
+
+    #[test]
+    fn test_unknown_parser_rejected() {
+        let result: Result<Config, _> = toml::from_str(
+            r#"
+            [scan.parsers]
+            mdx = "asciidoc"
+        "#,
+        );
+
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("asciidoc"));
+    }
+
+    #[test]
+    fn test_scan_syntax_validation() {
+        let scan = |body: &str| toml::from_str::<ScanConfig>(body).unwrap().syntax();
+
+        assert!(scan("").is_ok());
+        assert!(scan(r#"separator = "::""#).is_ok());
+        assert!(scan(r#"separator = " ""#)
+            .unwrap_err()
+            .contains("separator"));
+        assert!(scan(r##"separator = "#""##)
+            .unwrap_err()
+            .contains("separator"));
+        assert!(scan(r#"markers = [""]"#).unwrap_err().contains("markers"));
+        assert!(scan(r#"markers = ["a b"]"#)
+            .unwrap_err()
+            .contains("whitespace"));
+        assert!(scan(
+            r#"markers = ["Q::"]
+            separator = "::""#
+        )
+        .unwrap_err()
+        .contains("overlap"));
+        assert!(scan(r#"end_of_card = "  ""#)
+            .unwrap_err()
+            .contains("end_of_card"));
+        assert!(scan(r#"end_of_card = ":-:""#)
+            .unwrap_err()
+            .contains("end_of_card"));
+    }
 }
@@ -4,15 +4,118 @@ use std::{
 };
 
 use crate::{
-    algorithm::{CardState, OptimalFactorMatrix},
-    card::Card,
+    algorithm::{CardState, OptimalFactorMatrix, Quality},
+    card::{Card, CardKind, Directives},
+    similarity::similarity,
 };
 use anyhow::{bail, Context, Result};
 use chrono::{DateTime, Utc};
-use rusqlite::{params, Connection};
+use rusqlite::{
+    backup::Progress, params, params_from_iter, types::Value, Connection, Transaction,
+    TransactionBehavior, MAIN_DB,
+};
 use serde::{Deserialize, Serialize};
 
-const SCHEMA_VERSION: u32 = 1;
+const SCHEMA_VERSION: u32 = 9;
+
+/// The first versioned schema. Newer versions are reached by [`MIGRATIONS`].
+const SCHEMA_V1: &str = "
+    CREATE TABLE IF NOT EXISTS cards (
+        id BLOB NOT NULL PRIMARY KEY,
+        file TEXT NOT NULL,
+        line INTEGER NOT NULL,
+        prompt TEXT NOT NULL,
+        response TEXT NOT NULL,
+        tags TEXT NOT NULL,
+        added TEXT NOT NULL,
+        last_revised TEXT,
+        revise_count INTEGER NOT NULL DEFAULT 0,
+        leech INTEGER NOT NULL DEFAULT 0,
+        orphan INTEGER NOT NULL DEFAULT 0,
+        ease_factor REAL NOT NULL DEFAULT 2.5,
+        interval INTEGER NOT NULL DEFAULT 0,
+        repetitions INTEGER NOT NULL DEFAULT 0,
+        failed_count INTEGER NOT NULL DEFAULT 0
+    );
+    CREATE INDEX IF NOT EXISTS idx_cards_status ON cards (orphan, leech);
+    CREATE TABLE IF NOT EXISTS global_state (
+        id INTEGER PRIMARY KEY CHECK (id = 1),
+        optimal_factor_matrix TEXT NOT NULL DEFAULT '{}',
+        last_revise_session TEXT,
+        mean_q REAL,
+        total_cards_revised INTEGER NOT NULL DEFAULT 0
+    );
+    CREATE TABLE IF NOT EXISTS scan_index (
+        file_path TEXT PRIMARY KEY,
+        mtime INTEGER NOT NULL
+    );
+    INSERT OR IGNORE INTO global_state (id) VALUES (1);";
+
+/// `MIGRATIONS[n]` upgrades a database from version `n + 1` to `n + 2`.
+/// Never edit a released step; append a new one and bump [`SCHEMA_VERSION`].
+const MIGRATIONS: [&str; SCHEMA_VERSION as usize - 1] = [
+    // 2: card kinds
+    "ALTER TABLE cards ADD COLUMN kind TEXT NOT NULL DEFAULT '{\"type\":\"basic\"}';",
+    // 3: heading breadcrumbs
+    "ALTER TABLE cards ADD COLUMN breadcrumb TEXT NOT NULL DEFAULT '[]';",
+    // 4: hints
+    "ALTER TABLE cards ADD COLUMN hints TEXT NOT NULL DEFAULT '[]';
+     ALTER TABLE cards ADD COLUMN hints_used INTEGER NOT NULL DEFAULT 0;",
+    // 5: directives
+    "ALTER TABLE cards ADD COLUMN directives TEXT NOT NULL DEFAULT '{}';",
+    // 6: review log
+    "CREATE TABLE review_log (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        card_id BLOB NOT NULL,
+        reviewed_at TEXT NOT NULL,
+        quality INTEGER NOT NULL,
+        algorithm TEXT NOT NULL,
+        interval_before INTEGER NOT NULL,
+        interval_after INTEGER NOT NULL,
+        ease_before REAL NOT NULL,
+        ease_after REAL NOT NULL,
+        reversed INTEGER NOT NULL,
+        duration_ms INTEGER NOT NULL
+    );
+    CREATE INDEX idx_review_log_card ON review_log (card_id, reviewed_at);",
+    // 7: per-file scans
+    "CREATE INDEX idx_cards_file ON cards (file);",
+    // 8: due date, priority and tags queryable in SQL
+    "ALTER TABLE cards ADD COLUMN due_at INTEGER NOT NULL DEFAULT 0;
+     ALTER TABLE cards ADD COLUMN priority INTEGER NOT NULL DEFAULT 1;
+     ALTER TABLE cards ADD COLUMN suspended INTEGER NOT NULL DEFAULT 0;
+     UPDATE cards SET
+        due_at = COALESCE(unixepoch(last_revised) + 86400 * MIN(interval,
+            COALESCE(json_extract(directives, '$.max_interval'), interval)), 0),
+        priority = CASE json_extract(directives, '$.priority')
+            WHEN 'low' THEN 0 WHEN 'high' THEN 2 ELSE 1 END,
+        suspended = COALESCE(json_extract(directives, '$.suspended'), 0);
+     CREATE INDEX idx_cards_due ON cards (orphan, leech, suspended, due_at);
+     CREATE TABLE card_tags (
+        tag TEXT NOT NULL,
+        card_id BLOB NOT NULL,
+        PRIMARY KEY (tag, card_id)
+     ) WITHOUT ROWID;
+     CREATE INDEX idx_card_tags_card ON card_tags (card_id);
+     INSERT OR IGNORE INTO card_tags (tag, card_id)
+        SELECT tags.value, cards.id FROM cards, json_each(cards.tags) AS tags;
+     -- INSERT OR REPLACE doesn't fire delete triggers, so clear old tags here
+     CREATE TRIGGER card_tags_insert AFTER INSERT ON cards BEGIN
+        DELETE FROM card_tags WHERE card_id = NEW.id;
+        INSERT OR IGNORE INTO card_tags (tag, card_id)
+            SELECT value, NEW.id FROM json_each(NEW.tags);
+     END;
+     CREATE TRIGGER card_tags_update AFTER UPDATE OF id, tags ON cards BEGIN
+        DELETE FROM card_tags WHERE card_id = OLD.id;
+        INSERT OR IGNORE INTO card_tags (tag, card_id)
+            SELECT value, NEW.id FROM json_each(NEW.tags);
+     END;
+     CREATE TRIGGER card_tags_delete AFTER DELETE ON cards BEGIN
+        DELETE FROM card_tags WHERE card_id = OLD.id;
+     END;",
+    // 9: hints used per review
+    "ALTER TABLE review_log ADD COLUMN hints_used INTEGER NOT NULL DEFAULT 0;",
+];
 
 fn open_db(db_path: &Path) -> Result<Connection> {
     let conn = Connection::open(db_path)
@@ -21,52 +124,64 @@ fn open_db(db_path: &Path) -> Result<Connection> {
     Ok(conn)
 }
 
+fn schema_version(conn: &Connection) -> Result<u32> {
+    Ok(conn.pragma_query_value(None, "user_version", |row| row.get(0))?)
+}
+
+/// Bring the database up to [`SCHEMA_VERSION`], one transaction per step.
+/// An existing database is backed up before its first migration.
 fn ensure_schema(conn: &Connection) -> Result<()> {
-    let version: u32 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    let version = schema_version(conn)?;
     if version == SCHEMA_VERSION {
         return Ok(());
     }
-    if version != 0 {
-        // Drop and recreate — the db is a derived cache, not the source of truth.
-        conn.execute_batch(
-            "DROP TABLE IF EXISTS cards;
-             DROP TABLE IF EXISTS global_state;
-             DROP TABLE IF EXISTS scan_index;",
-        )?;
-    }
-    conn.execute_batch(
-        "CREATE TABLE IF NOT EXISTS cards (
-            id BLOB NOT NULL PRIMARY KEY,
-            file TEXT NOT NULL,
-            line INTEGER NOT NULL,
-            prompt TEXT NOT NULL,
-            response TEXT NOT NULL,
-            tags TEXT NOT NULL,
-            added TEXT NOT NULL,
-            last_revised TEXT,
-            revise_count INTEGER NOT NULL DEFAULT 0,
-            leech INTEGER NOT NULL DEFAULT 0,
-            orphan INTEGER NOT NULL DEFAULT 0,
-            ease_factor REAL NOT NULL DEFAULT 2.5,
-            interval INTEGER NOT NULL DEFAULT 0,
-            repetitions INTEGER NOT NULL DEFAULT 0,
-            failed_count INTEGER NOT NULL DEFAULT 0
+    if version > SCHEMA_VERSION {
+        bail!(
+            "Database schema version {version} is newer than this carddown supports \
+             ({SCHEMA_VERSION}). Please upgrade carddown."
         );
-        CREATE INDEX IF NOT EXISTS idx_cards_status ON cards (orphan, leech);
-        CREATE TABLE IF NOT EXISTS global_state (
-            id INTEGER PRIMARY KEY CHECK (id = 1),
-            optimal_factor_matrix TEXT NOT NULL DEFAULT '{}',
-            last_revise_session TEXT,
-            mean_q REAL,
-            total_cards_revised INTEGER NOT NULL DEFAULT 0
-        );
-        CREATE TABLE IF NOT EXISTS scan_index (
-            file_path TEXT PRIMARY KEY,
-            mtime INTEGER NOT NULL
-        );
-        INSERT OR IGNORE INTO global_state (id) VALUES (1);",
-    )?;
-    conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+    }
+    let fresh = version == 0;
+    if !fresh {
+        backup_db(conn, version)?;
+    }
+    loop {
+        // Lock before re-reading the version, another process may be migrating too
+        let tx = Transaction::new_unchecked(conn, TransactionBehavior::Immediate)?;
+        let version = schema_version(&tx)?;
+        if version >= SCHEMA_VERSION {
+            return Ok(());
+        }
+        let step = match version {
+            0 => SCHEMA_V1,
+            v => MIGRATIONS[v as usize - 1],
+        };
+        tx.execute_batch(step)
+            .with_context(|| format!("Failed to migrate database from version {version}"))?;
+        tx.pragma_update(None, "user_version", version + 1)?;
+        tx.commit()?;
+        if !fresh {
+            log::info!(
+                "Migrated database from version {version} to {}",
+                version + 1
+            );
+        }
+    }
+}
+
+/// Copy the database next to itself as `<name>.v<version>.bak`, unless a
+/// backup of that version already exists.
+fn backup_db(conn: &Connection, version: u32) -> Result<()> {
+    let Some(path) = conn.path().filter(|p| !p.is_empty()) else {
+        return Ok(());
+    };
+    let backup = format!("{path}.v{version}.bak");
+    if Path::new(&backup).exists() {
+        return Ok(());
+    }
+    conn.execute("VACUUM INTO ?1", [&backup])
+        .with_context(|| format!("Failed to back up database to {backup}"))?;
+    eprintln!("Backed up database to {backup} before upgrading it.");
     Ok(())
 }
 
@@ -78,6 +193,9 @@ pub struct CardEntry {
     pub leech: bool,
     pub orphan: bool,
     pub revise_count: u64,
+    /// Hints shown across all reviews of the card.
+    #[serde(default)]
+    pub hints_used: u64,
     pub state: CardState,
 }
 
@@ -90,9 +208,26 @@ impl CardEntry {
             leech: false,
             orphan: false,
             revise_count: 0,
+            hints_used: 0,
             state: CardState::default(),
         }
     }
+
+    /// Unix time the card is next due, honouring `#cd-max-interval`. Cards
+    /// never revised are due from the start.
+    pub fn due_at(&self) -> i64 {
+        let Some(last_revised) = self.last_revised else {
+            return 0;
+        };
+        let interval = match self.card.directives.max_interval {
+            Some(max_interval) => self.state.interval.min(max_interval),
+            None => self.state.interval,
+        };
+        let days = i64::try_from(interval).unwrap_or(i64::MAX);
+        last_revised
+            .timestamp()
+            .saturating_add(days.saturating_mul(86400))
+    }
 }
 
 pub type CardDb = HashMap<blake3::Hash, CardEntry>;
@@ -107,6 +242,26 @@ pub struct GlobalState {
 
 pub type ScanIndex = HashMap<String, u64>;
 
+/// One grade given during revise. The log outlives the cards it refers to.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReviewLogEntry {
+    pub card_id: blake3::Hash,
+    pub reviewed_at: DateTime<Utc>,
+    pub quality: Quality,
+    pub algorithm: String,
+    pub interval_before: u64,
+    pub interval_after: u64,
+    pub ease_before: f64,
+    pub ease_after: f64,
+    /// The card was asked response first.
+    pub reversed: bool,
+    /// Time from showing the card to grading it.
+    pub duration_ms: u64,
+    /// Hints shown before the grade was given.
+    #[serde(default)]
+    pub hints_used: u64,
+}
+
 // --- Card operations ---
 
 fn row_to_card_entry(row: &rusqlite::Row) -> rusqlite::Result<CardEntry> {
@@ -125,6 +280,11 @@ fn row_to_card_entry(row: &rusqlite::Row) -> rusqlite::Result<CardEntry> {
     let interval: i64 = row.get(12)?;
     let repetitions: i64 = row.get(13)?;
     let failed_count: i64 = row.get(14)?;
+    let kind_json: String = row.get(15)?;
+    let breadcrumb_json: String = row.get(16)?;
+    let hints_json: String = row.get(17)?;
+    let hints_used: i64 = row.get(18)?;
+    let directives_json: String = row.get(19)?;
 
     let hash_bytes: [u8; 32] = id_bytes.try_into().map_err(|_| {
         rusqlite::Error::FromSqlConversionFailure(
@@ -136,6 +296,10 @@ fn row_to_card_entry(row: &rusqlite::Row) -> rusqlite::Result<CardEntry> {
     let id = blake3::Hash::from_bytes(hash_bytes);
     let response: Vec<String> = serde_json::from_str(&response_json).unwrap_or_default();
     let tags: HashSet<String> = serde_json::from_str(&tags_json).unwrap_or_default();
+    let kind: CardKind = serde_json::from_str(&kind_json).unwrap_or_default();
+    let breadcrumb: Vec<String> = serde_json::from_str(&breadcrumb_json).unwrap_or_default();
+    let hints: Vec<String> = serde_json::from_str(&hints_json).unwrap_or_default();
+    let directives: Directives = serde_json::from_str(&directives_json).unwrap_or_default();
     let added = added_str
         .parse::<DateTime<Utc>>()
         .unwrap_or_else(|_| Utc::now());
@@ -150,11 +314,16 @@ fn row_to_card_entry(row: &rusqlite::Row) -> rusqlite::Result<CardEntry> {
             prompt,
             response,
             tags,
+            kind,
+            breadcrumb,
+            hints,
+            directives,
         },
         last_revised,
         leech,
         orphan,
         revise_count: revise_count as u64,
+        hints_used: hints_used as u64,
         state: CardState {
             ease_factor,
             interval: interval as u64,
@@ -171,18 +340,29 @@ pub fn get_db(db_path: &Path) -> Result<CardDb> {
     }
     let conn = open_db(db_path)?;
     ensure_schema(&conn)?;
+    query_cards(&conn, "", [])
+}
 
-    let mut stmt = conn.prepare(
-        "SELECT id, file, line, prompt, response, tags, added, last_revised,
-                revise_count, leech, orphan, ease_factor, interval, repetitions, failed_count
-         FROM cards",
-    )?;
+/// The columns [`row_to_card_entry`] reads, in order.
+const CARD_COLUMNS: &str = "id, file, line, prompt, response, tags, added, last_revised,
+    revise_count, leech, orphan, ease_factor, interval, repetitions, failed_count,
+    kind, breadcrumb, hints, hints_used, directives";
+
+/// Load the cards matching `filter`, a `WHERE` clause (or nothing).
+fn query_cards(conn: &Connection, filter: &str, params: impl rusqlite::Params) -> Result<CardDb> {
+    let mut stmt = conn.prepare_cached(&format!("SELECT {CARD_COLUMNS} FROM cards {filter}"))?;
     let entries = stmt
-        .query_map([], row_to_card_entry)?
+        .query_map(params, row_to_card_entry)?
         .collect::<Result<Vec<_>, _>>()?;
     Ok(entries.into_iter().map(|e| (e.card.id, e)).collect())
 }
 
+const UPSERT_CARD: &str = "INSERT OR REPLACE INTO cards (id, file, line, prompt, response, tags, added, last_revised,
+                                   revise_count, leech, orphan, ease_factor, interval, repetitions, failed_count,
+                                   kind, breadcrumb, hints, hints_used, directives, due_at, priority, suspended)
+     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17,
+             ?18, ?19, ?20, ?21, ?22, ?23)";
+
 pub fn write_db(db_path: &Path, db: &CardDb) -> Result<()> {
     let conn = open_db(db_path)?;
     ensure_schema(&conn)?;
@@ -192,8 +372,10 @@ pub fn write_db(db_path: &Path, db: &CardDb) -> Result<()> {
     {
         let mut stmt = tx.prepare(
             "INSERT INTO cards (id, file, line, prompt, response, tags, added, last_revised,
-                                revise_count, leech, orphan, ease_factor, interval, repetitions, failed_count)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+                                revise_count, leech, orphan, ease_factor, interval, repetitions, failed_count,
+                                kind, breadcrumb, hints, hints_used, directives, due_at, priority, suspended)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17,
+                     ?18, ?19, ?20, ?21, ?22, ?23)",
         )?;
         for entry in db.values() {
             insert_card_entry(&mut stmt, entry)?;
@@ -220,6 +402,14 @@ fn insert_card_entry(stmt: &mut rusqlite::Statement, entry: &CardEntry) -> Resul
         entry.state.interval as i64,
         entry.state.repetitions as i64,
         entry.state.failed_count as i64,
+        serde_json::to_string(&entry.card.kind)?,
+        serde_json::to_string(&entry.card.breadcrumb)?,
+        serde_json::to_string(&entry.card.hints)?,
+        entry.hints_used as i64,
+        serde_json::to_string(&entry.card.directives)?,
+        entry.due_at(),
+        entry.card.directives.priority as i64,
+        entry.card.directives.suspended,
     ])?;
     Ok(())
 }
@@ -237,35 +427,268 @@ pub fn delete_card(db_path: &Path, id: blake3::Hash) -> Result<()> {
     Ok(())
 }
 
-pub fn update_cards(db_path: &Path, cards: Vec<CardEntry>) -> Result<()> {
+/// Save a just graded card together with the global state and the review
+/// log entry, in one transaction. Only the review fields of the card are
+/// written, so a scan running meanwhile doesn't get its changes undone.
+pub fn record_review(
+    db_path: &Path,
+    entry: &CardEntry,
+    state: &GlobalState,
+    review: &ReviewLogEntry,
+) -> Result<()> {
     let conn = open_db(db_path)?;
     ensure_schema(&conn)?;
 
     let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "UPDATE cards SET last_revised = ?2, revise_count = ?3, leech = ?4, ease_factor = ?5,
+                          interval = ?6, repetitions = ?7, failed_count = ?8, hints_used = ?9,
+                          due_at = ?10
+         WHERE id = ?1",
+        params![
+            entry.card.id.as_bytes().as_slice(),
+            entry.last_revised.map(|d| d.to_rfc3339()),
+            entry.revise_count as i64,
+            entry.leech,
+            entry.state.ease_factor,
+            entry.state.interval as i64,
+            entry.state.repetitions as i64,
+            entry.state.failed_count as i64,
+            entry.hints_used as i64,
+            entry.due_at(),
+        ],
+    )?;
+    save_global_state(&tx, state)?;
+    insert_review(&tx, review)?;
+    tx.commit()?;
+    Ok(())
+}
+
+// --- Revise ---
+
+/// Which cards a revise session asks.
+#[derive(Debug, Clone, Default)]
+pub struct DueFilter {
+    /// Only cards carrying one of these tags or a descendant of one
+    /// (`lang/rust` matches `lang/rust/async`). Empty matches every card.
+    pub tags: HashSet<String>,
+    pub include_orphans: bool,
+    pub skip_leeches: bool,
+    /// Cram mode: cards not revised for this many hours are due, whatever
+    /// their interval.
+    pub cram_hours: Option<usize>,
+}
+
+impl DueFilter {
+    /// The `WHERE` clause selecting cards due at `now`, and its parameters.
+    fn to_sql(&self, now: DateTime<Utc>) -> (String, Vec<Value>) {
+        let mut clauses = vec!["suspended = 0".to_string()];
+        let mut params = vec![];
+        if !self.include_orphans {
+            clauses.push("orphan = 0".to_string());
+        }
+        if self.skip_leeches {
+            clauses.push("leech = 0".to_string());
+        }
+        match self.cram_hours {
+            Some(hours) => {
+                let hours = i64::try_from(hours).unwrap_or(i64::MAX);
+                let cutoff = now.timestamp().saturating_sub(hours.saturating_mul(3600));
+                clauses.push("COALESCE(unixepoch(last_revised), 0) <= ?".to_string());
+                params.push(Value::Integer(cutoff));
+            }
+            None => {
+                clauses.push("due_at <= ?".to_string());
+                params.push(Value::Integer(now.timestamp()));
+            }
+        }
+        if !self.tags.is_empty() {
+            let mut matches = vec![];
+            for tag in &self.tags {
+                let tag = tag.trim_end_matches('/');
+                // Descendants sort between `tag/` and `tag0`, '0' following '/'
+                matches.push("tag = ? OR (tag >= ? AND tag < ?)");
+                params.extend(
+                    [tag.to_string(), format!("{tag}/"), format!("{tag}0")].map(Value::Text),
+                );
+            }
+            clauses.push(format!(
+                "id IN (SELECT card_id FROM card_tags WHERE {})",
+                matches.join(" OR ")
+            ));
+        }
+        (clauses.join(" AND "), params)
+    }
+}
+
+/// Up to `limit` cards due at `now`, higher priority first and shuffled
+/// within a priority.
+pub fn due_cards(
+    db_path: &Path,
+    filter: &DueFilter,
+    now: DateTime<Utc>,
+    limit: usize,
+) -> Result<Vec<CardEntry>> {
+    if !db_path.exists() {
+        return Ok(vec![]);
+    }
+    let conn = open_db(db_path)?;
+    ensure_schema(&conn)?;
+    let (clause, mut params) = filter.to_sql(now);
+    params.push(Value::Integer(i64::try_from(limit).unwrap_or(i64::MAX)));
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {CARD_COLUMNS} FROM cards WHERE {clause}
+         ORDER BY priority DESC, RANDOM() LIMIT ?"
+    ))?;
+    let cards = stmt
+        .query_map(params_from_iter(params), row_to_card_entry)?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(cards)
+}
+
+/// Number of cards due at `now`.
+pub fn count_due(db_path: &Path, filter: &DueFilter, now: DateTime<Utc>) -> Result<usize> {
+    if !db_path.exists() {
+        return Ok(0);
+    }
+    let conn = open_db(db_path)?;
+    ensure_schema(&conn)?;
+    let (clause, params) = filter.to_sql(now);
+    let count: i64 = conn.query_row(
+        &format!("SELECT COUNT(*) FROM cards WHERE {clause}"),
+        params_from_iter(params),
+        |row| row.get(0),
+    )?;
+    Ok(count as usize)
+}
+
+/// Move cards to new ids, keeping their review history. Used when cards
+/// gain identity anchors. Ids that are missing, or whose target already
+/// exists, are left alone. Returns the number of cards re-keyed.
+pub fn rekey_cards(db_path: &Path, mapping: &[(blake3::Hash, blake3::Hash)]) -> Result<usize> {
+    if mapping.is_empty() || !db_path.exists() {
+        return Ok(0);
+    }
+    let conn = open_db(db_path)?;
+    ensure_schema(&conn)?;
+
+    let tx = conn.unchecked_transaction()?;
+    let mut moved = vec![];
     {
         let mut stmt = tx.prepare(
-            "INSERT OR REPLACE INTO cards (id, file, line, prompt, response, tags, added, last_revised,
-                                           revise_count, leech, orphan, ease_factor, interval, repetitions, failed_count)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+            "UPDATE cards SET id = ?2 WHERE id = ?1
+             AND NOT EXISTS (SELECT 1 FROM cards WHERE id = ?2)",
         )?;
-        for entry in &cards {
-            insert_card_entry(&mut stmt, entry)?;
+        for (old, new) in mapping {
+            if stmt.execute(params![
+                old.as_bytes().as_slice(),
+                new.as_bytes().as_slice()
+            ])? > 0
+            {
+                moved.push((*old, *new));
+            }
         }
     }
+    rekey_review_log(&tx, &moved)?;
     tx.commit()?;
+    Ok(moved.len())
+}
+
+/// Point the review log entries of re-keyed cards at their new ids.
+fn rekey_review_log(conn: &Connection, mapping: &[(blake3::Hash, blake3::Hash)]) -> Result<()> {
+    let mut stmt = conn.prepare("UPDATE review_log SET card_id = ?2 WHERE card_id = ?1")?;
+    for (old, new) in mapping {
+        stmt.execute(params![
+            old.as_bytes().as_slice(),
+            new.as_bytes().as_slice()
+        ])?;
+    }
     Ok(())
 }
 
 // --- Scan stats ---
 
+/// Cards within this many lines of a vanished card are considered edits of it.
+const MIGRATION_LINE_WINDOW: u64 = 5;
+/// Minimum text similarity for a new card to be treated as an edit.
+const MIGRATION_MIN_SIMILARITY: f64 = 0.7;
+
 pub struct ScanStats {
     pub found: usize,
     pub new: usize,
     pub updated: usize,
     pub orphaned: usize,
     pub unorphaned: usize,
+    pub migrated: Vec<Migration>,
+}
+
+/// A card that vanished from a file, paired with the new card that most
+/// likely replaced it after an edit.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Migration {
+    pub from: Card,
+    pub to: Card,
+    pub similarity: f64,
 }
 
+/// Whether two cards are of the same kind, ignoring cloze groups (which
+/// change whenever the sentence is edited). The forward side of a sided card
+/// counts as basic, so making a card double-sided keeps its history.
+fn same_kind(a: &CardKind, b: &CardKind) -> bool {
+    let forward = |k: &CardKind| matches!(k, CardKind::Basic | CardKind::Sided { reversed: false });
+    match (a, b) {
+        (CardKind::Cloze { index: a, .. }, CardKind::Cloze { index: b, .. }) => a == b,
+        _ if forward(a) || forward(b) => forward(a) && forward(b),
+        _ => a == b,
+    }
+}
+
+fn card_text(card: &Card) -> String {
+    format!("{}\n{}", card.prompt, card.response.join("\n"))
+}
+
+/// Pair new cards with vanished cards from the same file that sit near the
+/// same line and have very similar text. Each card is used at most once,
+/// best matches first.
+fn find_migrations(vanished: &[&CardEntry], new: &[&CardEntry]) -> Vec<Migration> {
+    let mut candidates = Vec::new();
+    for old in vanished {
+        for new in new {
+            let (old, new) = (&old.card, &new.card);
+            if old.file != new.file
+                || old.line.abs_diff(new.line) > MIGRATION_LINE_WINDOW
+                || !same_kind(&old.kind, &new.kind)
+            {
+                continue;
+            }
+            let similarity = similarity(&card_text(old), &card_text(new));
+            if similarity >= MIGRATION_MIN_SIMILARITY {
+                candidates.push((similarity, old, new));
+            }
+        }
+    }
+    candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    let mut used = HashSet::new();
+    let mut migrations = Vec::new();
+    for (similarity, old, new) in candidates {
+        if used.contains(&old.id) || used.contains(&new.id) {
+            continue;
+        }
+        used.insert(old.id);
+        used.insert(new.id);
+        migrations.push(Migration {
+            from: old.clone(),
+            to: new.clone(),
+            similarity,
+        });
+    }
+    migrations
+}
+
+/// Merge the cards found by a scan into the database. Only the cards of the
+/// scanned files are loaded, and only rows that actually change are written.
+/// A `full` scan also flags every card that wasn't found as an orphan.
 pub fn update_db(
     db_path: &Path,
     found_cards: Vec<Card>,
@@ -280,25 +703,71 @@ pub fn update_db(
             updated: 0,
             orphaned: 0,
             unorphaned: 0,
+            migrated: vec![],
         });
     }
 
-    let mut card_db: CardDb = if !db_path.exists() {
-        HashMap::new()
+    // Dry runs work on an in-memory copy, so not even a schema upgrade sticks
+    let conn = if dry_run {
+        let mut conn = Connection::open_in_memory()?;
+        if db_path.exists() {
+            conn.restore(MAIN_DB, db_path, None::<fn(Progress)>)
+                .with_context(|| format!("Failed to read database: {}", db_path.display()))?;
+        }
+        conn
     } else {
-        get_db(db_path)?
+        open_db(db_path)?
     };
+    ensure_schema(&conn)?;
+    let tx = conn.unchecked_transaction()?;
 
     let mut found_card_db: CardDb = found_cards
         .into_iter()
         .map(|card| (card.id, CardEntry::new(card)))
         .collect();
     let found_ids: HashSet<_> = found_card_db.keys().cloned().collect();
+    let found_files: HashSet<_> = found_card_db
+        .values()
+        .map(|e| e.card.file.clone())
+        .collect();
+
+    // Only cards of the scanned files, and found cards that moved in from
+    // elsewhere, can change. A full scan looks at everything for orphans, and
+    // one big read beats many lookups when a scan covers much of the vault.
+    let stored: i64 = tx.query_row("SELECT COUNT(*) FROM cards", [], |row| row.get(0))?;
+    let mut card_db = if full || found_ids.len() as i64 >= stored / 2 {
+        query_cards(&tx, "", [])?
+    } else {
+        let mut card_db = CardDb::new();
+        for file in &found_files {
+            card_db.extend(query_cards(
+                &tx,
+                "WHERE file = ?1",
+                [file.to_string_lossy()],
+            )?);
+        }
+        let moved_in: Vec<_> = found_ids
+            .iter()
+            .filter(|id| !card_db.contains_key(*id))
+            .collect();
+        for id in moved_in {
+            card_db.extend(query_cards(
+                &tx,
+                "WHERE id = ?1",
+                [id.as_bytes().as_slice()],
+            )?);
+        }
+        card_db
+    };
 
     let mut new_ctr = 0;
     let mut orphan_ctr = 0;
     let mut unorphan_ctr = 0;
     let mut updated_ctr = 0;
+    // Rows to write; everything else in the db stays untouched
+    let mut upserts: Vec<blake3::Hash> = vec![];
+    let mut deletes: Vec<blake3::Hash> = vec![];
+    let mut orphans: Vec<blake3::Hash> = vec![];
 
     let common_ids: Vec<_> = found_ids
         .iter()
@@ -308,17 +777,47 @@ pub fn update_db(
     for id in &common_ids {
         let mut entry = card_db.remove(id).unwrap();
         let new = found_card_db.remove(id).unwrap();
+        let mut changed = false;
         if entry.card != new.card {
             entry.card = new.card;
             updated_ctr += 1;
+            changed = true;
         }
         if entry.orphan {
             entry.orphan = false;
             unorphan_ctr += 1;
+            changed = true;
+        }
+        if changed {
+            upserts.push(*id);
         }
         card_db.insert(*id, entry);
     }
 
+    // Cards that disappeared from a scanned file may have been edited in place;
+    // carry their history over to the most similar new card nearby.
+    let vanished: Vec<_> = card_db
+        .values()
+        .filter(|e| !found_ids.contains(&e.card.id) && found_files.contains(&e.card.file))
+        .collect();
+    let candidates: Vec<_> = found_card_db
+        .values()
+        .filter(|e| !card_db.contains_key(&e.card.id))
+        .collect();
+    let migrations = find_migrations(&vanished, &candidates);
+    for migration in &migrations {
+        let old = card_db.remove(&migration.from.id).unwrap();
+        let mut entry = found_card_db.remove(&migration.to.id).unwrap();
+        entry.state = old.state;
+        entry.revise_count = old.revise_count;
+        entry.hints_used = old.hints_used;
+        entry.last_revised = old.last_revised;
+        entry.added = old.added;
+        deletes.push(old.card.id);
+        upserts.push(entry.card.id);
+        card_db.insert(entry.card.id, entry);
+    }
+
     let new_ids: Vec<_> = found_ids
         .iter()
         .filter(|id| !card_db.contains_key(id))
@@ -326,18 +825,14 @@ pub fn update_db(
         .collect();
     for id in &new_ids {
         card_db.insert(*id, found_card_db.remove(id).unwrap());
+        upserts.push(*id);
         new_ctr += 1;
     }
 
     if full {
-        let orphan_ids: Vec<_> = card_db
-            .keys()
-            .filter(|id| !found_ids.contains(id))
-            .cloned()
-            .collect();
-        for id in &orphan_ids {
-            if let Some(entry) = card_db.get_mut(id) {
-                entry.orphan = true;
+        for entry in card_db.values().filter(|e| !found_ids.contains(&e.card.id)) {
+            if !entry.orphan {
+                orphans.push(entry.card.id);
             }
             orphan_ctr += 1;
         }
@@ -354,20 +849,46 @@ pub fn update_db(
     if orphan_ctr > 0 {
         log::debug!("Found {orphan_ctr} orphaned cards");
     }
+    if !migrations.is_empty() {
+        log::debug!("Migrated history of {} edited cards", migrations.len());
+    }
     if unorphan_ctr > 0 {
         log::debug!("Unorphaned {unorphan_ctr} cards");
     }
 
-    let found = found_ids.len();
+    {
+        let mut delete = tx.prepare("DELETE FROM cards WHERE id = ?1")?;
+        for id in &deletes {
+            delete.execute([id.as_bytes().as_slice()])?;
+        }
+        // In key order, so big scans fill the table sequentially
+        upserts.sort_unstable_by_key(|id| *id.as_bytes());
+        let mut upsert = tx.prepare(UPSERT_CARD)?;
+        for id in &upserts {
+            insert_card_entry(&mut upsert, &card_db[id])?;
+        }
+        let mut orphan = tx.prepare("UPDATE cards SET orphan = 1 WHERE id = ?1")?;
+        for id in &orphans {
+            orphan.execute([id.as_bytes().as_slice()])?;
+        }
+    }
+    let moved: Vec<_> = migrations.iter().map(|m| (m.from.id, m.to.id)).collect();
+    rekey_review_log(&tx, &moved)?;
+    log::debug!(
+        "Wrote {} card rows",
+        deletes.len() + upserts.len() + orphans.len()
+    );
     if !dry_run {
-        write_db(db_path, &card_db)?;
+        tx.commit()?;
     }
+
     Ok(ScanStats {
-        found,
+        found: found_ids.len(),
         new: new_ctr,
         updated: updated_ctr,
         orphaned: orphan_ctr,
         unorphaned: unorphan_ctr,
+        migrated: migrations,
     })
 }
 
@@ -425,7 +946,10 @@ pub fn refresh_global_state(state: &mut GlobalState) {
 pub fn write_global_state(db_path: &Path, state: &GlobalState) -> Result<()> {
     let conn = open_db(db_path)?;
     ensure_schema(&conn)?;
+    save_global_state(&conn, state)
+}
 
+fn save_global_state(conn: &Connection, state: &GlobalState) -> Result<()> {
     let ofm_json = serde_json::to_string(&state.optimal_factor_matrix)?;
     conn.execute(
         "UPDATE global_state SET optimal_factor_matrix = ?1, last_revise_session = ?2,
@@ -440,19 +964,91 @@ pub fn write_global_state(db_path: &Path, state: &GlobalState) -> Result<()> {
     Ok(())
 }
 
+// --- Review log ---
+
+fn insert_review(conn: &Connection, entry: &ReviewLogEntry) -> Result<()> {
+    conn.execute(
+        "INSERT INTO review_log (card_id, reviewed_at, quality, algorithm, interval_before,
+                                 interval_after, ease_before, ease_after, reversed, duration_ms,
+                                 hints_used)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        params![
+            entry.card_id.as_bytes().as_slice(),
+            entry.reviewed_at.to_rfc3339(),
+            entry.quality as i64,
+            entry.algorithm,
+            entry.interval_before as i64,
+            entry.interval_after as i64,
+            entry.ease_before,
+            entry.ease_after,
+            entry.reversed,
+            entry.duration_ms as i64,
+            entry.hints_used as i64,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Every logged review, oldest first.
+pub fn get_review_log(db_path: &Path) -> Result<Vec<ReviewLogEntry>> {
+    if !db_path.exists() {
+        return Ok(vec![]);
+    }
+    let conn = open_db(db_path)?;
+    ensure_schema(&conn)?;
+
+    let mut stmt = conn.prepare(
+        "SELECT card_id, reviewed_at, quality, algorithm, interval_before, interval_after,
+                ease_before, ease_after, reversed, duration_ms, hints_used
+         FROM review_log ORDER BY id",
+    )?;
+    let entries = stmt
+        .query_map([], |row| {
+            let id_bytes: Vec<u8> = row.get(0)?;
+            let reviewed_at: String = row.get(1)?;
+            let quality: u8 = row.get(2)?;
+            let invalid = |col, what: &str| {
+                rusqlite::Error::FromSqlConversionFailure(
+                    col,
+                    rusqlite::types::Type::Integer,
+                    what.to_string().into(),
+                )
+            };
+            let hash_bytes: [u8; 32] = id_bytes
+                .try_into()
+                .map_err(|_| invalid(0, "invalid hash length"))?;
+            Ok(ReviewLogEntry {
+                card_id: blake3::Hash::from_bytes(hash_bytes),
+                reviewed_at: reviewed_at
+                    .parse::<DateTime<Utc>>()
+                    .map_err(|_| invalid(1, "invalid timestamp"))?,
+                quality: Quality::from_grade(quality).ok_or_else(|| invalid(2, "invalid grade"))?,
+                algorithm: row.get(3)?,
+                interval_before: row.get::<_, i64>(4)? as u64,
+                interval_after: row.get::<_, i64>(5)? as u64,
+                ease_before: row.get(6)?,
+                ease_after: row.get(7)?,
+                reversed: row.get(8)?,
+                duration_ms: row.get::<_, i64>(9)? as u64,
+                hints_used: row.get::<_, i64>(10)? as u64,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(entries)
+}
+
 // --- Scan index ---
 
 pub fn load_scan_index(db_path: &Path) -> ScanIndex {
     if !db_path.exists() {
         return HashMap::new();
     }
-    let conn = match open_db(db_path) {
+    // Opened as is, without migrating or switching to WAL, so `scan --dry-run`
+    // leaves the db untouched. The scan index is unchanged since v1.
+    let conn = match Connection::open(db_path) {
         Ok(c) => c,
         Err(_) => return HashMap::new(),
     };
-    if ensure_schema(&conn).is_err() {
-        return HashMap::new();
-    }
 
     let mut stmt = match conn.prepare("SELECT file_path, mtime FROM scan_index") {
         Ok(s) => s,
@@ -583,46 +1179,52 @@ pub fn export_json_state(state: &GlobalState) -> Result<String> {
     serde_json::to_string_pretty(state).context("Failed to serialise state to JSON")
 }
 
+/// Export the review log to JSON format.
+pub fn export_json_review_log(log: &[ReviewLogEntry]) -> Result<String> {
+    serde_json::to_string_pretty(log).context("Failed to serialise review log to JSON")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use ordered_float::OrderedFloat;
     use tempfile::NamedTempFile;
 
-    fn get_card_entries() -> Vec<CardEntry> {
-        let card = Card {
-            id: blake3::hash(b"foo"),
-            file: Path::new("foo").to_path_buf(),
-            line: 0,
-            prompt: "foo".to_string(),
-            response: vec!["bar".to_string()],
-            tags: HashSet::from(["foo".to_string()]),
-        };
-        let card2 = Card {
-            id: blake3::hash(b"baz"),
-            file: Path::new("baz").to_path_buf(),
+    fn named_card(name: &str) -> Card {
+        Card {
+            id: blake3::hash(name.as_bytes()),
+            file: Path::new(name).to_path_buf(),
             line: 0,
-            prompt: "baz".to_string(),
+            prompt: name.to_string(),
             response: vec!["bar".to_string()],
-            tags: HashSet::from(["baz".to_string()]),
-        };
+            tags: HashSet::from([name.to_string()]),
+            kind: CardKind::Basic,
+            breadcrumb: vec![],
+            hints: vec![],
+            directives: Default::default(),
+        }
+    }
+
+    fn get_card_entries() -> Vec<CardEntry> {
         vec![
             CardEntry {
                 added: "2012-12-12T12:12:12Z".parse::<DateTime<Utc>>().unwrap(),
-                card,
+                card: named_card("foo"),
                 last_revised: None,
                 leech: false,
                 orphan: true,
                 revise_count: 1,
+                hints_used: 0,
                 state: CardState::default(),
             },
             CardEntry {
                 added: "2011-11-11T11:11:11Z".parse::<DateTime<Utc>>().unwrap(),
-                card: card2,
+                card: named_card("baz"),
                 last_revised: "2012-12-12T12:12:12Z".parse::<DateTime<Utc>>().ok(),
                 leech: true,
                 orphan: false,
                 revise_count: 2,
+                hints_used: 0,
                 state: CardState::default(),
             },
         ]
@@ -649,6 +1251,145 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_card_kind_roundtrip() {
+        let mut entries = get_card_entries();
+        entries[0].card.kind = CardKind::Cloze {
+            group: blake3::hash(b"group"),
+            index: 2,
+        };
+        let (file, db) = write_a_db(entries);
+        let read_db = get_db(file.path()).unwrap();
+        for (id, entry) in &db {
+            assert_eq!(entry.card.kind, read_db.get(id).unwrap().card.kind);
+        }
+    }
+
+    #[test]
+    fn test_hints_roundtrip() {
+        let mut entries = get_card_entries();
+        entries[0].card.hints = vec!["Starts with P".to_string()];
+        entries[0].hints_used = 3;
+        let (file, db) = write_a_db(entries);
+        let read_db = get_db(file.path()).unwrap();
+        for (id, entry) in &db {
+            assert_eq!(entry, read_db.get(id).unwrap());
+        }
+    }
+
+    /// Databases as written by each past schema version, oldest first.
+    const SCHEMA_FIXTURES: [&str; SCHEMA_VERSION as usize - 1] = [
+        include_str!("../tests/db_fixtures/v1.sql"),
+        include_str!("../tests/db_fixtures/v2.sql"),
+        include_str!("../tests/db_fixtures/v3.sql"),
+        include_str!("../tests/db_fixtures/v4.sql"),
+        include_str!("../tests/db_fixtures/v5.sql"),
+        include_str!("../tests/db_fixtures/v6.sql"),
+        include_str!("../tests/db_fixtures/v7.sql"),
+        include_str!("../tests/db_fixtures/v8.sql"),
+    ];
+
+    fn fixture_db(sql: &str) -> (tempfile::TempDir, std::path::PathBuf) {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("carddown.db");
+        Connection::open(&path).unwrap().execute_batch(sql).unwrap();
+        (dir, path)
+    }
+
+    fn version_of(path: &Path) -> u32 {
+        schema_version(&Connection::open(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_migrate_from_every_version() {
+        for (idx, sql) in SCHEMA_FIXTURES.iter().enumerate() {
+            let version = idx as u32 + 1;
+            let (_dir, path) = fixture_db(sql);
+
+            let db = get_db(&path).unwrap();
+            assert_eq!(version_of(&path), SCHEMA_VERSION, "from v{version}");
+            assert_eq!(db.len(), 2, "from v{version}");
+            let entry = &db[&blake3::Hash::from_bytes([0x11; 32])];
+            assert_eq!(entry.card.prompt, "Capital of France?");
+            assert_eq!(entry.card.kind, CardKind::Basic);
+            assert_eq!(entry.revise_count, 7);
+            assert_eq!(entry.state.interval, 21);
+            assert_eq!(entry.state.repetitions, 6);
+            assert_eq!(entry.state.ease_factor, 2.36);
+            assert!(entry.last_revised.is_some());
+            assert_eq!(entry.card.breadcrumb.len(), usize::from(version >= 3));
+            assert_eq!(entry.hints_used, if version >= 4 { 2 } else { 0 });
+            let priority = entry.card.directives.priority;
+            assert_eq!(priority == crate::card::Priority::High, version >= 5);
+            let gone = &db[&blake3::Hash::from_bytes([0x22; 32])];
+            assert!(gone.orphan && gone.leech);
+            assert_eq!(gone.state.failed_count, 4);
+
+            assert_eq!(get_global_state(&path).unwrap().mean_q, Some(3.5));
+            assert_eq!(load_scan_index(&path).len(), 1);
+            let conn = Connection::open(&path).unwrap();
+            let due_at: i64 = conn
+                .query_row(
+                    "SELECT due_at FROM cards WHERE id = ?1",
+                    [entry.card.id.as_bytes().as_slice()],
+                    |row| row.get(0),
+                )
+                .unwrap();
+            assert_eq!(due_at, entry.due_at(), "from v{version}");
+            let geo = DueFilter {
+                tags: HashSet::from(["geo".to_string()]),
+                ..Default::default()
+            };
+            assert_eq!(count_due(&path, &geo, Utc::now()).unwrap(), 1);
+            let logged = usize::from(version >= 6);
+            assert_eq!(get_review_log(&path).unwrap().len(), logged);
+
+            let backup = path.with_file_name(format!("carddown.db.v{version}.bak"));
+            assert_eq!(version_of(&backup), version);
+        }
+    }
+
+    #[test]
+    fn test_new_db_has_no_backup() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("carddown.db");
+        write_global_state(&path, &GlobalState::default()).unwrap();
+        assert_eq!(version_of(&path), SCHEMA_VERSION);
+        assert!(!std::fs::read_dir(dir.path()).unwrap().any(|f| f
+            .unwrap()
+            .path()
+            .extension()
+            .is_some_and(|e| e == "bak")));
+    }
+
+    #[test]
+    fn test_failed_migration_rolls_back() {
+        // A v4 db that somehow already has the column added by v5
+        let sql = SCHEMA_FIXTURES[3].replace(
+            "PRAGMA user_version = 4;",
+            "ALTER TABLE cards ADD COLUMN directives TEXT;\nPRAGMA user_version = 4;",
+        );
+        let (_dir, path) = fixture_db(&sql);
+        let err = get_db(&path).unwrap_err();
+        assert!(format!("{err:#}").contains("from version 4"), "{err:#}");
+        assert_eq!(version_of(&path), 4);
+        let conn = Connection::open(&path).unwrap();
+        let cards: i64 = conn
+            .query_row("SELECT COUNT(*) FROM cards", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(cards, 2);
+    }
+
+    #[test]
+    fn test_newer_schema_is_rejected() {
+        let (_dir, path) = fixture_db(
+            &SCHEMA_FIXTURES[4].replace("PRAGMA user_version = 5;", "PRAGMA user_version = 99;"),
+        );
+        let err = get_db(&path).unwrap_err();
+        assert!(err.to_string().contains("newer"), "{err}");
+        assert_eq!(version_of(&path), 99);
+    }
+
     #[test]
     fn test_get_global_state() {
         let file = NamedTempFile::new().unwrap();
@@ -699,13 +1440,134 @@ mod tests {
     }
 
     #[test]
-    fn test_update_cards() {
+    fn test_record_review() {
+        let (file, _) = write_a_db(get_card_entries());
+        let mut entry = get_card_entries().pop().unwrap();
+        entry.state.interval = 1;
+        entry.revise_count += 1;
+        entry.last_revised = Some(Utc::now());
+        entry.card.prompt = "stale prompt".to_string();
+        let state = GlobalState {
+            mean_q: Some(4.0),
+            total_cards_revised: 1,
+            ..Default::default()
+        };
+        let review = review_of(entry.card.id, Quality::Perfect);
+        record_review(file.path(), &entry, &state, &review).unwrap();
+
+        let read_db = get_db(file.path()).unwrap();
+        let read = read_db.get(&entry.card.id).unwrap();
+        assert_eq!(read.state.interval, 1);
+        assert_eq!(read.revise_count, entry.revise_count);
+        assert!(read.last_revised.is_some());
+        // Card content is owned by scans
+        assert_ne!(read.card.prompt, entry.card.prompt);
+        assert_eq!(get_global_state(file.path()).unwrap().mean_q, Some(4.0));
+        assert_eq!(get_review_log(file.path()).unwrap(), vec![review]);
+    }
+
+    #[test]
+    fn test_due_cards_follow_writes() {
         let (file, _) = write_a_db(get_card_entries());
+        let now = Utc::now();
+        let tagged = |tag: &str| DueFilter {
+            tags: HashSet::from([tag.to_string()]),
+            include_orphans: true,
+            ..Default::default()
+        };
+        let due = |filter: &DueFilter| count_due(file.path(), filter, now).unwrap();
+        assert_eq!(due(&DueFilter::default()), 1);
+        assert_eq!(due(&tagged("baz")), 1);
+
         let mut entry = get_card_entries().pop().unwrap();
         entry.state.interval = 1;
-        update_cards(file.path(), vec![entry.clone()]).unwrap();
+        entry.last_revised = Some(now);
+        let review = review_of(entry.card.id, Quality::Perfect);
+        record_review(file.path(), &entry, &GlobalState::default(), &review).unwrap();
+        assert_eq!(due(&tagged("baz")), 0);
+        let tomorrow = now + chrono::Duration::days(1);
+        assert_eq!(count_due(file.path(), &tagged("baz"), tomorrow).unwrap(), 1);
+
+        let new_id = blake3::hash(b"anchored");
+        rekey_cards(file.path(), &[(blake3::hash(b"foo"), new_id)]).unwrap();
+        assert_eq!(due(&tagged("foo")), 1);
+        let mut moved = get_db(file.path()).unwrap().remove(&new_id).unwrap();
+        moved.card.tags = HashSet::from(["qux".to_string()]);
+        update_db(file.path(), vec![moved.card], false, false).unwrap();
+        assert_eq!(due(&tagged("foo")), 0);
+        assert_eq!(due(&tagged("qux")), 1);
+        delete_card(file.path(), new_id).unwrap();
+        assert_eq!(due(&tagged("qux")), 0);
+        let conn = Connection::open(file.path()).unwrap();
+        let tags: i64 = conn
+            .query_row("SELECT COUNT(*) FROM card_tags", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(tags, 1);
+    }
+
+    #[test]
+    fn test_rekey_cards() {
+        let (file, _) = write_a_db(get_card_entries());
+        let entry = get_card_entries().remove(1);
+        let new_id = blake3::hash(b"anchored");
+        let mapping = [
+            (entry.card.id, new_id),
+            (blake3::hash(b"missing"), blake3::hash(b"other")),
+        ];
+        assert_eq!(rekey_cards(file.path(), &mapping).unwrap(), 1);
         let read_db = get_db(file.path()).unwrap();
-        assert_eq!(read_db.get(&entry.card.id).unwrap().state.interval, 1);
+        assert!(!read_db.contains_key(&entry.card.id));
+        let moved = read_db.get(&new_id).unwrap();
+        assert_eq!(moved.revise_count, entry.revise_count);
+        assert_eq!(moved.last_revised, entry.last_revised);
+    }
+
+    fn log_review(db_path: &Path, review: &ReviewLogEntry) -> Result<()> {
+        insert_review(&open_db(db_path)?, review)
+    }
+
+    fn review_of(card_id: blake3::Hash, quality: Quality) -> ReviewLogEntry {
+        ReviewLogEntry {
+            card_id,
+            reviewed_at: "2024-01-02T03:04:05Z".parse::<DateTime<Utc>>().unwrap(),
+            quality,
+            algorithm: "sm2".to_string(),
+            interval_before: 1,
+            interval_after: 6,
+            ease_before: 2.5,
+            ease_after: 2.6,
+            reversed: true,
+            duration_ms: 4200,
+            hints_used: 0,
+        }
+    }
+
+    #[test]
+    fn test_review_log_roundtrip() {
+        let (file, _) = write_a_db(get_card_entries());
+        let id = get_card_entries()[0].card.id;
+        let first = review_of(id, Quality::Perfect);
+        let second = ReviewLogEntry {
+            hints_used: 2,
+            ..review_of(id, Quality::IncorrectButRemembered)
+        };
+        log_review(file.path(), &first).unwrap();
+        log_review(file.path(), &second).unwrap();
+        assert_eq!(get_review_log(file.path()).unwrap(), vec![first, second]);
+    }
+
+    #[test]
+    fn test_review_log_follows_rekeyed_cards() {
+        let (file, _) = write_a_db(get_card_entries());
+        let id = get_card_entries()[0].card.id;
+        log_review(file.path(), &review_of(id, Quality::Perfect)).unwrap();
+        let new_id = blake3::hash(b"anchored");
+        rekey_cards(file.path(), &[(id, new_id)]).unwrap();
+        assert_eq!(get_review_log(file.path()).unwrap()[0].card_id, new_id);
+
+        // Logged reviews survive the card being deleted
+        delete_card(file.path(), new_id).unwrap();
+        assert_eq!(get_review_log(file.path()).unwrap().len(), 1);
     }
 
     #[test]
@@ -721,6 +1583,173 @@ mod tests {
         );
     }
 
+    fn capital_card(id: &[u8], line: u64, response: &str) -> Card {
+        Card {
+            id: blake3::hash(id),
+            file: Path::new("notes.md").to_path_buf(),
+            line,
+            prompt: "What is the capital of France?".to_string(),
+            response: vec![response.to_string()],
+            tags: HashSet::new(),
+            kind: CardKind::Basic,
+            breadcrumb: vec![],
+            hints: vec![],
+            directives: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_update_db_migrates_edited_card() {
+        let mut entry = CardEntry::new(capital_card(b"old", 3, "Paris"));
+        entry.revise_count = 5;
+        entry.state.interval = 10;
+        entry.last_revised = Some(Utc::now());
+        entry.added = "2012-12-12T12:12:12Z".parse::<DateTime<Utc>>().unwrap();
+        let (file, _) = write_a_db(vec![entry.clone()]);
+        log_review(file.path(), &review_of(entry.card.id, Quality::Perfect)).unwrap();
+
+        let edited = capital_card(b"new", 4, "Paris.");
+        let stats = update_db(file.path(), vec![edited.clone()], true, false).unwrap();
+        assert_eq!(stats.migrated.len(), 1);
+        assert_eq!(stats.migrated[0].from, entry.card);
+        assert_eq!(stats.migrated[0].to, edited);
+        assert_eq!(stats.new, 0);
+        assert_eq!(stats.orphaned, 0);
+
+        let read_db = get_db(file.path()).unwrap();
+        assert_eq!(read_db.len(), 1);
+        let migrated = read_db.get(&edited.id).unwrap();
+        assert_eq!(migrated.card.response, vec!["Paris."]);
+        assert_eq!(migrated.revise_count, 5);
+        assert_eq!(migrated.state.interval, 10);
+        assert_eq!(migrated.added, entry.added);
+        assert!(migrated.last_revised.is_some());
+        assert_eq!(get_review_log(file.path()).unwrap()[0].card_id, edited.id);
+    }
+
+    #[test]
+    fn test_same_kind_sided() {
+        let forward = CardKind::Sided { reversed: false };
+        let backward = CardKind::Sided { reversed: true };
+        assert!(same_kind(&CardKind::Basic, &forward));
+        assert!(same_kind(&backward, &backward));
+        assert!(!same_kind(&CardKind::Basic, &backward));
+        assert!(!same_kind(&forward, &backward));
+    }
+
+    #[test]
+    fn test_update_db_does_not_migrate_unrelated_cards() {
+        let (file, _) = write_a_db(vec![CardEntry::new(capital_card(b"old", 3, "Paris"))]);
+
+        // Too far away
+        let moved = capital_card(b"moved", 30, "Paris.");
+        let stats = update_db(file.path(), vec![moved], false, true).unwrap();
+        assert!(stats.migrated.is_empty());
+        assert_eq!(stats.new, 1);
+
+        // Too different
+        let mut other = capital_card(b"other", 3, "Paris");
+        other.prompt = "Largest planet?".to_string();
+        other.response = vec!["Jupiter".to_string()];
+        let stats = update_db(file.path(), vec![other], false, true).unwrap();
+        assert!(stats.migrated.is_empty());
+
+        // Different file
+        let mut elsewhere = capital_card(b"elsewhere", 3, "Paris.");
+        elsewhere.file = Path::new("other.md").to_path_buf();
+        let stats = update_db(file.path(), vec![elsewhere], false, true).unwrap();
+        assert!(stats.migrated.is_empty());
+    }
+
+    fn rowids(path: &Path) -> HashMap<Vec<u8>, i64> {
+        let conn = Connection::open(path).unwrap();
+        let mut stmt = conn.prepare("SELECT id, rowid FROM cards").unwrap();
+        stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_update_db_only_writes_changed_rows() {
+        let paris = capital_card(b"paris", 3, "Paris");
+        let mut rome = capital_card(b"rome", 9, "Rome");
+        rome.prompt = "Capital of Italy?".to_string();
+        let mut elsewhere = capital_card(b"oslo", 3, "Oslo");
+        elsewhere.file = Path::new("other.md").to_path_buf();
+        let (file, _) = write_a_db(
+            [&paris, &rome, &elsewhere]
+                .into_iter()
+                .map(|c| CardEntry::new(c.clone()))
+                .collect(),
+        );
+        let before = rowids(file.path());
+
+        // Rescanning unchanged cards writes nothing
+        let stats = update_db(file.path(), vec![paris.clone(), rome.clone()], true, false).unwrap();
+        assert_eq!(stats.updated, 0);
+        assert_eq!(rowids(file.path()), before);
+
+        // Changing one card only rewrites that card
+        rome.line = 10;
+        update_db(file.path(), vec![paris.clone(), rome.clone()], false, false).unwrap();
+        let after = rowids(file.path());
+        assert_ne!(
+            after[rome.id.as_bytes().as_slice()],
+            before[rome.id.as_bytes().as_slice()]
+        );
+        for card in [&paris, &elsewhere] {
+            let id = card.id.as_bytes().as_slice();
+            assert_eq!(after[id], before[id]);
+        }
+        assert_eq!(get_db(file.path()).unwrap()[&rome.id].card.line, 10);
+    }
+
+    #[test]
+    fn test_update_db_dry_run_writes_nothing() {
+        let (file, db) = write_a_db(vec![CardEntry::new(capital_card(b"old", 3, "Paris"))]);
+        let stats = update_db(
+            file.path(),
+            vec![
+                capital_card(b"new", 3, "Paris."),
+                capital_card(b"x", 40, "?"),
+            ],
+            true,
+            true,
+        )
+        .unwrap();
+        assert_eq!(stats.migrated.len(), 1);
+        assert_eq!(stats.new, 1);
+        assert_eq!(get_db(file.path()).unwrap(), db);
+
+        // Nor upgrades an old db
+        let (dir, path) = fixture_db(SCHEMA_FIXTURES[0]);
+        let before = std::fs::read(&path).unwrap();
+        assert_eq!(load_scan_index(&path).len(), 1);
+        update_db(&path, vec![capital_card(b"x", 1, "?")], false, true).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), before);
+        assert_eq!(version_of(&path), 1);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        // Nor creates a db
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("carddown.db");
+        let stats = update_db(&path, vec![capital_card(b"x", 1, "?")], false, true).unwrap();
+        assert_eq!(stats.new, 1);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_find_migrations_prefers_best_match() {
+        let old = CardEntry::new(capital_card(b"old", 3, "Paris"));
+        let close = CardEntry::new(capital_card(b"close", 3, "Paris!"));
+        let closer = CardEntry::new(capital_card(b"closer", 4, "Paris"));
+        let migrations = find_migrations(&[&old], &[&close, &closer]);
+        assert_eq!(migrations.len(), 1);
+        assert_eq!(migrations[0].to.id, closer.card.id);
+        assert_eq!(migrations[0].similarity, 1.0);
+    }
+
     #[test]
     fn test_update_db_unorphan() {
         let (file, _) = write_a_db(get_card_entries());
@@ -736,14 +1765,7 @@ mod tests {
         let (file, _) = write_a_db(get_card_entries());
         let entry = get_card_entries().remove(1);
         assert!(!entry.orphan);
-        let card = Card {
-            id: blake3::hash(b"new"),
-            file: Path::new("new").to_path_buf(),
-            line: 0,
-            prompt: "new".to_string(),
-            response: vec!["new".to_string()],
-            tags: HashSet::from(["new".to_string()]),
-        };
+        let card = named_card("new");
         update_db(file.path(), vec![card], true, false).unwrap();
         let read_db = get_db(file.path()).unwrap();
         assert!(read_db.get(&entry.card.id).unwrap().orphan);
@@ -752,14 +1774,7 @@ mod tests {
     #[test]
     fn test_update_db_new_card() {
         let (file, db) = write_a_db(get_card_entries());
-        let card = Card {
-            id: blake3::hash(b"new"),
-            file: Path::new("new").to_path_buf(),
-            line: 0,
-            prompt: "new".to_string(),
-            response: vec!["new".to_string()],
-            tags: HashSet::from(["new".to_string()]),
-        };
+        let card = named_card("new");
         update_db(file.path(), vec![card], false, false).unwrap();
         let read_db = get_db(file.path()).unwrap();
         assert_eq!(db.len() + 1, read_db.len());
@@ -780,14 +1795,7 @@ mod tests {
     #[test]
     fn test_update_db_with_duplicate_cards() {
         let (file, _) = write_a_db(vec![]);
-        let card = Card {
-            id: blake3::hash(b"duplicate"),
-            file: Path::new("test").to_path_buf(),
-            line: 0,
-            prompt: "test".to_string(),
-            response: vec!["test".to_string()],
-            tags: HashSet::new(),
-        };
+        let card = named_card("duplicate");
         let cards = vec![card.clone(), card.clone()];
         update_db(file.path(), cards, true, false).unwrap();
         let read_db = get_db(file.path()).unwrap();
@@ -816,14 +1824,7 @@ mod tests {
         use crate::algorithm::{new_algorithm, Algo, Quality};
 
         let file = NamedTempFile::new().unwrap();
-        let card = Card {
-            id: blake3::hash(b"test_extreme"),
-            file: std::path::PathBuf::from("test.md"),
-            line: 1,
-            prompt: "test".to_string(),
-            response: vec!["test".to_string()],
-            tags: HashSet::new(),
-        };
+        let card = named_card("test_extreme");
         let mut entry = CardEntry::new(card);
         entry.state.interval = u64::MAX - 1000;
         entry.state.ease_factor = 10.0;
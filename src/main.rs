@@ -2,19 +2,21 @@ mod algorithm;
 mod card;
 mod config;
 mod db;
+mod parser;
+mod similarity;
 mod vault;
 mod view;
 
 use crate::algorithm::Algo;
 use crate::card::Card;
 use crate::db::CardDb;
-use crate::db::CardEntry;
+use crate::parser::ParserRegistry;
 use crate::vault::VaultPaths;
 use algorithm::new_algorithm;
 use anyhow::{Context, Result};
 use clap::{Parser, Subcommand, ValueEnum};
 use env_logger::Env;
-use rand::prelude::*;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
 use serde::Deserialize;
 use std::collections::HashSet;
 use std::fs::OpenOptions;
@@ -94,6 +96,11 @@ enum Commands {
         #[arg(long)]
         dry_run: bool,
 
+        /// Write a stable identity anchor (e.g. `^cd-3fa2b1`) next to every card
+        /// that lacks one, so later edits to the card keep its review history
+        #[arg(long)]
+        assign_ids: bool,
+
         /// Path to a file or directory to scan for flashcards
         path: PathBuf,
     },
@@ -125,7 +132,8 @@ enum Commands {
         #[arg(short = 'a', long, value_enum)]
         algorithm: Option<Algo>,
 
-        /// Only show cards with these tags (shows all cards if no tags specified)
+        /// Only show cards with these tags or their descendants, e.g. `lang/rust`
+        /// also matches `lang/rust/async` (shows all cards if no tags specified)
         #[arg(short = 't', long)]
         tag: Vec<String>,
 
@@ -145,6 +153,15 @@ enum Commands {
         /// Hours since last review for cards to include in cram mode
         #[arg(long, default_value_t = defaults::CRAM_HOURS)]
         cram_hours: usize,
+
+        /// Type in the answer to every card and get a suggested grade, not
+        /// just for cards tagged #type
+        #[arg(long)]
+        type_answers: bool,
+
+        /// Offer the first letters of the answer as a last hint
+        #[arg(long)]
+        letter_hints: bool,
     },
     /// Import review history from another carddown database.
     ///
@@ -166,7 +183,7 @@ enum Commands {
     },
     /// Export the current vault database to JSON format.
     ///
-    /// Writes cards.json and state.json to the specified directory.
+    /// Writes cards.json, state.json and review_log.json to the specified directory.
     /// Useful for backup, migration, or interoperability with other tools.
     Export {
         /// Directory to write the exported JSON files to
@@ -207,6 +224,7 @@ enum ResolvedCommand {
         file_types: Vec<String>,
         full: bool,
         dry_run: bool,
+        assign_ids: bool,
         path: PathBuf,
     },
     Audit {},
@@ -221,6 +239,8 @@ enum ResolvedCommand {
         reverse_probability: f64,
         cram: bool,
         cram_hours: usize,
+        type_answers: bool,
+        letter_hints: bool,
     },
     Import {
         source: PathBuf,
@@ -246,81 +266,93 @@ fn mtime_secs(path: &std::path::Path) -> Option<u64> {
         .map(|d| d.as_secs())
 }
 
-fn file_types_to_set(file_types: &[String]) -> HashSet<&str> {
-    HashSet::from_iter(file_types.iter().map(|s| s.as_str()))
+/// Load the gitignore-style patterns in `.carddownignore` at the vault root.
+fn load_scan_ignore(root: &Path) -> Gitignore {
+    let path = root.join(vault::IGNORE_FILE);
+    let mut builder = GitignoreBuilder::new(root);
+    if path.exists() {
+        if let Some(e) = builder.add(&path) {
+            log::warn!("Error reading {}: {e}", path.display());
+        }
+    }
+    builder.build().unwrap_or_else(|e| {
+        log::warn!("Error reading {}: {e}", path.display());
+        Gitignore::empty()
+    })
 }
 
-fn collect_files(folder: &Path, file_types: &HashSet<&str>) -> Vec<PathBuf> {
+fn collect_files(folder: &Path, registry: &ParserRegistry, ignore: &Gitignore) -> Vec<PathBuf> {
+    // Ignore patterns are rooted at the (canonical) vault root
+    let abs_folder = folder
+        .canonicalize()
+        .unwrap_or_else(|_| folder.to_path_buf());
     WalkDir::new(folder)
         .into_iter()
+        .filter_entry(|e| {
+            let path = e
+                .path()
+                .strip_prefix(folder)
+                .map(|rel| abs_folder.join(rel))
+                .unwrap_or_else(|_| e.path().to_path_buf());
+            !ignore.matched(&path, e.file_type().is_dir()).is_ignore()
+        })
         .filter_map(Result::ok)
         .filter(|e| e.file_type().is_file())
-        .filter(|e| {
-            e.path()
-                .extension()
-                .and_then(|ext| ext.to_str())
-                .is_some_and(|ext| file_types.contains(ext))
-        })
+        .filter(|e| registry.handles(e.path()))
         .map(|e| e.path().to_path_buf())
         .collect()
 }
 
 #[cfg(test)]
-fn parse_cards_from_folder(folder: &Path, file_types: &[String]) -> Result<Vec<Card>> {
-    let file_types_set = file_types_to_set(file_types);
-    collect_files(folder, &file_types_set)
+fn parse_cards_from_folder(folder: &Path, registry: &ParserRegistry) -> Result<Vec<Card>> {
+    collect_files(folder, registry, &Gitignore::empty())
         .into_iter()
         .try_fold(Vec::new(), |mut acc, path| {
-            let mut cards = card::parse_file(&path)?;
+            let mut cards = registry.parse_file(&path)?;
             acc.append(&mut cards);
             Ok(acc)
         })
 }
 
-fn filter_cards(
-    db: CardDb,
-    tags: HashSet<String>,
-    include_orphans: bool,
-    leech_method: LeechMethod,
-    cram_mode: bool,
-    cram_hours: usize,
-) -> Vec<CardEntry> {
-    let today = chrono::Utc::now();
-    db.into_values()
-        .filter(|c| is_card_due(c, today, cram_mode, cram_hours))
-        .filter(|c| matches_tags(c, &tags))
-        .filter(|c| include_orphans || !c.orphan)
-        .filter(|c| !should_skip_leech(c, leech_method))
-        .collect()
+/// Write identity anchors into `files` and move the matching database
+/// entries to their anchored ids. Returns the number of cards anchored.
+fn assign_card_ids(
+    files: &[PathBuf],
+    registry: &ParserRegistry,
+    vault: &VaultPaths,
+    dry_run: bool,
+) -> Result<usize> {
+    // Anchors must be unique across the vault, not just among `files`
+    let mut taken = HashSet::new();
+    for f in collect_files(&vault.root, registry, &load_scan_ignore(&vault.root)) {
+        taken.extend(registry.anchors(&f)?);
+    }
+    let mut mapping = Vec::new();
+    for f in files {
+        mapping.append(&mut registry.assign_anchors(f, &mut taken, dry_run)?);
+    }
+    if !dry_run {
+        db::rekey_cards(&vault.db_path, &mapping)?;
+    }
+    Ok(mapping.len())
 }
 
-fn is_card_due(
-    card: &CardEntry,
-    today: chrono::DateTime<chrono::Utc>,
+/// What `revise` asks, from its command line.
+fn due_filter(
+    tags: &[String],
+    include_orphans: bool,
+    leech_method: LeechMethod,
     cram_mode: bool,
     cram_hours: usize,
-) -> bool {
-    match card.last_revised {
-        Some(last_revised) => {
-            if cram_mode {
-                today - last_revised >= chrono::Duration::hours(cram_hours as i64)
-            } else {
-                let next_date = last_revised + chrono::Duration::days(card.state.interval as i64);
-                today >= next_date
-            }
-        }
-        None => true,
+) -> db::DueFilter {
+    db::DueFilter {
+        tags: tags.iter().cloned().collect(),
+        include_orphans,
+        skip_leeches: matches!(leech_method, LeechMethod::Skip),
+        cram_hours: cram_mode.then_some(cram_hours),
     }
 }
 
-fn matches_tags(card: &CardEntry, tags: &HashSet<String>) -> bool {
-    tags.is_empty() || !card.card.tags.is_disjoint(tags)
-}
-
-fn should_skip_leech(card: &CardEntry, leech_method: LeechMethod) -> bool {
-    matches!(leech_method, LeechMethod::Skip) && card.leech
-}
-
 /// Resolve vault paths from CLI args.
 fn resolve_vault(args: &Args) -> VaultPaths {
     if let Some(vault_path) = &args.vault {
@@ -343,6 +375,7 @@ fn resolve_args(args: Args, config: &config::Config) -> ResolvedArgs {
             file_types,
             full,
             dry_run,
+            assign_ids,
             path,
         } => ResolvedCommand::Scan {
             file_types: file_types
@@ -350,6 +383,7 @@ fn resolve_args(args: Args, config: &config::Config) -> ResolvedArgs {
                 .unwrap_or_else(|| vec!["md".to_string(), "txt".to_string(), "org".to_string()]),
             full,
             dry_run,
+            assign_ids,
             path,
         },
         Commands::Audit {} => ResolvedCommand::Audit {},
@@ -364,6 +398,8 @@ fn resolve_args(args: Args, config: &config::Config) -> ResolvedArgs {
             reverse_probability,
             cram,
             cram_hours,
+            type_answers,
+            letter_hints,
         } => ResolvedCommand::Revise {
             maximum_cards_per_session: maximum_cards_per_session
                 .or(config.revise.maximum_cards_per_session)
@@ -387,6 +423,8 @@ fn resolve_args(args: Args, config: &config::Config) -> ResolvedArgs {
                 .unwrap_or(defaults::REVERSE_PROBABILITY),
             cram,
             cram_hours,
+            type_answers: type_answers || config.revise.type_answers.unwrap_or(false),
+            letter_hints: letter_hints || config.revise.letter_hints.unwrap_or(false),
         },
         Commands::Import { source, dry_run } => ResolvedCommand::Import { source, dry_run },
         Commands::Export { output_dir } => ResolvedCommand::Export { output_dir },
@@ -435,6 +473,7 @@ fn import_stats(source_path: &Path, target_path: &Path, dry_run: bool) -> Result
                 target_entry.state = source_entry.state.clone();
                 target_entry.last_revised = source_entry.last_revised;
                 target_entry.revise_count = source_entry.revise_count;
+                target_entry.hints_used = source_entry.hints_used;
                 target_entry.leech = source_entry.leech;
                 updated += 1;
             }
@@ -480,12 +519,16 @@ fn main() -> Result<()> {
             file_types,
             full,
             dry_run,
+            assign_ids,
             path,
         } => {
+            let prefix = if dry_run { "[dry-run] " } else { "" };
+            let syntax = config.scan.syntax().map_err(anyhow::Error::msg)?;
+            let registry = ParserRegistry::new(&file_types, &config.scan.parsers, syntax);
             let all_cards = if path.is_dir() {
-                let file_types_set = file_types_to_set(&file_types);
                 let mut index = db::load_scan_index(&vault.db_path);
-                let files = collect_files(&path, &file_types_set);
+                let ignore = load_scan_ignore(&vault.root);
+                let files = collect_files(&path, &registry, &ignore);
                 let to_scan: Vec<PathBuf> = if full {
                     files
                 } else {
@@ -507,9 +550,16 @@ fn main() -> Result<()> {
                     }
                     modified
                 };
+                if assign_ids {
+                    let anchored = assign_card_ids(&to_scan, &registry, &vault, dry_run)?;
+                    eprintln!("{prefix}Assigned ids to {anchored} card(s)");
+                    for f in &to_scan {
+                        index.insert(f.to_string_lossy().to_string(), mtime_secs(f).unwrap_or(0));
+                    }
+                }
                 let mut acc: Vec<Card> = Vec::new();
                 for f in &to_scan {
-                    let mut cs = card::parse_file(f)?;
+                    let mut cs = registry.parse_file(f)?;
                     acc.append(&mut cs);
                 }
                 if !dry_run {
@@ -517,18 +567,22 @@ fn main() -> Result<()> {
                 }
                 acc
             } else if path.is_file() {
+                if assign_ids {
+                    let anchored =
+                        assign_card_ids(std::slice::from_ref(&path), &registry, &vault, dry_run)?;
+                    eprintln!("{prefix}Assigned ids to {anchored} card(s)");
+                }
                 if !dry_run {
                     let mut index = db::load_scan_index(&vault.db_path);
                     let m = mtime_secs(&path).unwrap_or(0);
                     index.insert(path.to_string_lossy().to_string(), m);
                     db::save_scan_index(&vault.db_path, &index);
                 }
-                card::parse_file(&path)?
+                registry.parse_file(&path)?
             } else {
                 vec![]
             };
             let stats = db::update_db(&vault.db_path, all_cards, full, dry_run)?;
-            let prefix = if dry_run { "[dry-run] " } else { "" };
             let mut parts = vec![format!("{prefix}Found {} card(s)", stats.found)];
             if stats.new > 0 {
                 parts.push(format!("{} new", stats.new));
@@ -542,7 +596,24 @@ fn main() -> Result<()> {
             if stats.unorphaned > 0 {
                 parts.push(format!("{} restored", stats.unorphaned));
             }
+            if !stats.migrated.is_empty() {
+                parts.push(format!("{} migrated", stats.migrated.len()));
+            }
             eprintln!("{}", parts.join(", "));
+            if dry_run {
+                for m in &stats.migrated {
+                    eprintln!(
+                        "{prefix}{}:{} {:?} -> {}:{} {:?} ({:.0}% similar)",
+                        m.from.file.display(),
+                        m.from.line + 1,
+                        m.from.prompt,
+                        m.to.file.display(),
+                        m.to.line + 1,
+                        m.to.prompt,
+                        m.similarity * 100.0
+                    );
+                }
+            }
         }
         ResolvedCommand::Audit {} => {
             let db = db::get_db(&vault.db_path)?;
@@ -571,31 +642,28 @@ fn main() -> Result<()> {
             maximum_duration_of_session,
             reverse_probability,
             tag: tags,
+            type_answers,
+            letter_hints,
         } => {
-            let db = db::get_db(&vault.db_path)?;
             let mut state = db::get_global_state(&vault.db_path)?;
             db::refresh_global_state(&mut state);
-            let tags_set: HashSet<String> = tags.iter().cloned().collect();
-            let mut cards = filter_cards(
-                db,
-                tags_set,
-                include_orphans,
-                leech_method,
-                cram,
-                cram_hours,
-            );
-            cards.shuffle(&mut rand::rng());
-            let cards: Vec<_> = cards.into_iter().take(maximum_cards_per_session).collect();
+            let filter = due_filter(&tags, include_orphans, leech_method, cram, cram_hours);
+            let now = chrono::Utc::now();
+            let cards = db::due_cards(&vault.db_path, &filter, now, maximum_cards_per_session)?;
             if cards.is_empty() {
                 eprintln!("No cards due for review.");
                 return Ok(());
             }
             ensure_tui_available(args.no_tty, "revise")?;
-            eprintln!("{} card(s) due for review.", cards.len());
+            let due = db::count_due(&vault.db_path, &filter, now)?;
+            if due > cards.len() {
+                eprintln!("{due} card(s) due for review, asking {}.", cards.len());
+            } else {
+                eprintln!("{} card(s) due for review.", cards.len());
+            }
             let total_cards = cards.len();
             let mut terminal = view::init()?;
             let db_path = vault.db_path.clone();
-            let db_path2 = vault.db_path.clone();
             let mut app = view::revise::App::new(
                 new_algorithm(algorithm),
                 cards,
@@ -605,20 +673,25 @@ fn main() -> Result<()> {
                     max_duration: maximum_duration_of_session,
                     reverse_probability,
                     tags,
+                    type_answers,
+                    letter_hints,
                 },
-                Box::new(move |cards, state| {
+                Box::new(move |card, state, review| {
                     if !cram {
-                        let _ = db::update_cards(&db_path, cards);
-                        db::write_global_state(&db_path2, state)?;
+                        db::record_review(&db_path, card, state, review)?;
                     }
                     Ok(())
                 }),
             );
             let res = app.run(&mut terminal);
             let reviewed = app.cards_reviewed();
+            let save_failures = app.save_failures();
             view::restore()?;
             res?;
-            eprintln!("Reviewed {reviewed}/{total_cards} card(s).")
+            eprintln!("Reviewed {reviewed}/{total_cards} card(s).");
+            if save_failures > 0 {
+                eprintln!("Warning: {save_failures} grade(s) could not be saved.");
+            }
         }
         ResolvedCommand::Import { source, dry_run } => {
             if !source.exists() {
@@ -642,13 +715,18 @@ fn main() -> Result<()> {
             let db = db::get_db(&vault.db_path)?;
             let state = db::get_global_state(&vault.db_path)?;
 
+            let review_log = db::get_review_log(&vault.db_path)?;
+
             let cards_json = db::export_json_cards(&db)?;
             let state_json = db::export_json_state(&state)?;
+            let review_log_json = db::export_json_review_log(&review_log)?;
 
             let cards_path = output_dir.join("cards.json");
             let state_path = output_dir.join("state.json");
+            let review_log_path = output_dir.join("review_log.json");
             std::fs::write(&cards_path, cards_json)?;
             std::fs::write(&state_path, state_json)?;
+            std::fs::write(&review_log_path, review_log_json)?;
 
             eprintln!("Exported {} card(s) to {}", db.len(), output_dir.display());
         }
@@ -660,13 +738,16 @@ fn main() -> Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::card::{CardKind, Priority, Syntax};
+    use crate::db::CardEntry;
     use clap::Parser;
+    use std::collections::HashMap;
 
     #[test]
     fn test_parse_cards_from_folder() {
         let folder = PathBuf::from("tests/fixtures");
-        let file_types = vec!["md".to_string()];
-        let cards = parse_cards_from_folder(&folder, &file_types).unwrap();
+        let registry = ParserRegistry::new(&["md".to_string()], &HashMap::new(), Syntax::default());
+        let cards = parse_cards_from_folder(&folder, &registry).unwrap();
         assert_eq!(cards.len(), 4);
         assert!(cards.iter().any(|c| c.prompt == "Capital of France?"));
         assert!(cards.iter().any(|c| c.prompt == "Explain photosynthesis"));
@@ -676,11 +757,58 @@ mod tests {
     #[test]
     fn test_parse_cards_from_folder_type_filter() {
         let folder = PathBuf::from("tests/fixtures");
-        let file_types = vec!["txt".to_string()];
-        let cards = parse_cards_from_folder(&folder, &file_types).unwrap();
+        let registry =
+            ParserRegistry::new(&["txt".to_string()], &HashMap::new(), Syntax::default());
+        let cards = parse_cards_from_folder(&folder, &registry).unwrap();
         assert!(cards.is_empty());
     }
 
+    #[test]
+    fn test_collect_files_honours_carddownignore() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let root = tmp.path().canonicalize().unwrap();
+        for f in [
+            "notes/a.md",
+            "notes/drafts/b.md",
+            "node_modules/pkg/c.md",
+            "templates/d.md",
+            "e.draft.md",
+            "templates/keep.md",
+        ] {
+            let path = root.join(f);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+        std::fs::write(
+            root.join(vault::IGNORE_FILE),
+            "node_modules/\ndrafts/\n/templates/*\n!/templates/keep.md\n*.draft.md\n",
+        )
+        .unwrap();
+        let registry = ParserRegistry::new(&["md".to_string()], &HashMap::new(), Syntax::default());
+        let ignore = load_scan_ignore(&root);
+
+        let names = |folder: &Path| {
+            let mut files: Vec<_> = collect_files(folder, &registry, &ignore)
+                .into_iter()
+                .map(|f| f.file_name().unwrap().to_string_lossy().to_string())
+                .collect();
+            files.sort();
+            files
+        };
+        assert_eq!(names(&root), vec!["a.md", "keep.md"]);
+        assert_eq!(names(&root.join("notes")), vec!["a.md"]);
+        assert_eq!(names(&tmp.path().join("notes/../notes")), vec!["a.md"]);
+    }
+
+    #[test]
+    fn test_parse_cards_from_folder_mapped_extension() {
+        let folder = PathBuf::from("tests/fixtures");
+        let extra = HashMap::from([("md".to_string(), parser::ParserKind::Text)]);
+        let registry = ParserRegistry::new(&[], &extra, Syntax::default());
+        let cards = parse_cards_from_folder(&folder, &registry).unwrap();
+        assert_eq!(cards.len(), 4);
+    }
+
     fn parse_args(argv: &[&str]) -> Args {
         Args::parse_from(argv)
     }
@@ -697,6 +825,8 @@ mod tests {
             leech_method = "warn"
             algorithm = "sm2"
             reverse_probability = 0.5
+            type_answers = true
+            letter_hints = true
         "#,
         )
         .unwrap();
@@ -709,12 +839,16 @@ mod tests {
             leech_method,
             algorithm,
             reverse_probability,
+            type_answers,
+            letter_hints,
             ..
         } = resolved.command
         else {
             panic!("expected revise command");
         };
 
+        assert!(type_answers);
+        assert!(letter_hints);
         assert_eq!(maximum_cards_per_session, 12);
         assert_eq!(maximum_duration_of_session, 9);
         assert_eq!(leech_failure_threshold, 6);
@@ -785,6 +919,10 @@ mod tests {
             prompt: "What is the answer to life, the universe, and everything?".to_string(),
             response: vec!["42".to_string()],
             tags: HashSet::from(["card".to_string()]),
+            kind: CardKind::Basic,
+            breadcrumb: vec![],
+            hints: vec![],
+            directives: Default::default(),
         };
         let entry = CardEntry::new(card);
         db.insert(entry.card.id, entry);
@@ -799,7 +937,11 @@ mod tests {
         cram: bool,
         cram_hours: usize,
     ) -> Vec<CardEntry> {
-        filter_cards(db, tags, orphans, leech, cram, cram_hours)
+        let file = tempfile::NamedTempFile::new().unwrap();
+        db::write_db(file.path(), &db).unwrap();
+        let tags: Vec<String> = tags.into_iter().collect();
+        let filter = due_filter(&tags, orphans, leech, cram, cram_hours);
+        db::due_cards(file.path(), &filter, chrono::Utc::now(), usize::MAX).unwrap()
     }
 
     fn run_filter_defaults(db: CardDb) -> Vec<CardEntry> {
@@ -844,6 +986,59 @@ mod tests {
         assert_eq!(run_filter_defaults(db).len(), 1);
     }
 
+    #[test]
+    fn test_filter_cards_max_interval_directive() {
+        let mut db = get_card_db();
+        let entry = db.get_mut(&blake3::hash(b"test")).unwrap();
+        entry.state.interval = 30;
+        entry.last_revised = Some(chrono::Utc::now() - chrono::Duration::days(7));
+        entry.card.directives.max_interval = Some(7);
+        assert_eq!(run_filter_defaults(db).len(), 1);
+    }
+
+    #[test]
+    fn test_filter_cards_suspended() {
+        let mut db = get_card_db();
+        let entry = db.get_mut(&blake3::hash(b"test")).unwrap();
+        entry.card.directives.suspended = true;
+        assert!(run_filter_defaults(db.clone()).is_empty());
+        assert!(run_filter(db, HashSet::new(), true, LeechMethod::Warn, true, 0).is_empty());
+    }
+
+    #[test]
+    fn test_due_cards_by_priority() {
+        let entry = get_card_db().into_values().next().unwrap();
+        let db: CardDb = [Priority::Low, Priority::High, Priority::Normal]
+            .into_iter()
+            .cycle()
+            .take(30)
+            .enumerate()
+            .map(|(i, priority)| {
+                let mut entry = entry.clone();
+                entry.card.id = blake3::hash(&[i as u8]);
+                entry.card.directives.priority = priority;
+                (entry.card.id, entry)
+            })
+            .collect();
+        let file = tempfile::NamedTempFile::new().unwrap();
+        db::write_db(file.path(), &db).unwrap();
+        let filter = due_filter(&[], false, LeechMethod::Skip, false, 12);
+        let now = chrono::Utc::now();
+        let asked = |limit| {
+            db::due_cards(file.path(), &filter, now, limit)
+                .unwrap()
+                .into_iter()
+                .map(|c| c.card.directives.priority)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(asked(10), vec![Priority::High; 10]);
+        let priorities = asked(30);
+        assert!(priorities[..10].iter().all(|p| *p == Priority::High));
+        assert!(priorities[10..20].iter().all(|p| *p == Priority::Normal));
+        assert!(priorities[20..].iter().all(|p| *p == Priority::Low));
+        assert_eq!(db::count_due(file.path(), &filter, now).unwrap(), 30);
+    }
+
     #[test]
     fn test_filter_cards_cram_mode() {
         let mut db = get_card_db();
@@ -880,6 +1075,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_filter_cards_hierarchical_tags() {
+        let mut db = get_card_db();
+        db.get_mut(&blake3::hash(b"test")).unwrap().card.tags =
+            HashSet::from(["lang/rust/async".to_string()]);
+        let filter = |tag: &str| {
+            let tags = HashSet::from([tag.to_string()]);
+            run_filter(db.clone(), tags, false, LeechMethod::Skip, false, 12).len()
+        };
+        assert_eq!(filter("lang"), 1);
+        assert_eq!(filter("lang/rust"), 1);
+        assert_eq!(filter("lang/rust/"), 1);
+        assert_eq!(filter("lang/rust/async"), 1);
+        assert_eq!(filter("lang/ru"), 0);
+        assert_eq!(filter("lang/rust/async/tokio"), 0);
+        assert_eq!(filter("rust"), 0);
+    }
+
     #[test]
     fn test_filter_cards_non_matching_tags() {
         let tags = HashSet::from(["foo".to_string(), "test".to_string()]);
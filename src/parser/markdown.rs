@@ -0,0 +1,123 @@
+use anyhow::Result;
+use std::collections::HashSet;
+use std::path::Path;
+
+use super::CardParser;
+use crate::card::{self, Card, Syntax};
+
+pub struct Markdown {}
+
+impl CardParser for Markdown {
+    fn parse(&self, file: &Path, contents: &str, syntax: &Syntax) -> Result<Vec<Card>> {
+        let Some((len, tags)) = front_matter(contents) else {
+            return card::parse_lines(file, contents, syntax);
+        };
+        // Blank the front matter so its delimiters and values can't start or
+        // end cards, keeping line numbers intact
+        let body: Vec<&str> = contents
+            .lines()
+            .enumerate()
+            .map(|(i, l)| if i < len { "" } else { l })
+            .collect();
+        let mut cards = card::parse_lines(file, &body.join("\n"), syntax)?;
+        for card in &mut cards {
+            card.tags.extend(tags.iter().cloned());
+        }
+        Ok(cards)
+    }
+    fn name(&self) -> &'static str {
+        "markdown"
+    }
+}
+
+/// Find YAML front matter at the start of `contents`. Returns its length in
+/// lines (including the `---` delimiters) and its `tags`.
+///
+/// Only the tag list is read; both the inline (`tags: [a, b]` or
+/// `tags: a, b`) and block (`- a` per line) forms are supported.
+fn front_matter(contents: &str) -> Option<(usize, HashSet<String>)> {
+    let mut lines = contents.lines();
+    if lines.next()?.trim_end() != "---" {
+        return None;
+    }
+    let body: Vec<&str> = lines
+        .take_while(|l| !matches!(l.trim_end(), "---" | "..."))
+        .collect();
+    if body.len() + 1 == contents.lines().count() {
+        // Never closed, so this is not front matter
+        return None;
+    }
+
+    let mut tags = HashSet::new();
+    let mut in_tags = false;
+    for line in &body {
+        if in_tags {
+            match line.trim_start().strip_prefix('-') {
+                Some(item) if line.starts_with([' ', '-']) => {
+                    tags.extend(tag_values(item));
+                    continue;
+                }
+                _ => in_tags = false,
+            }
+        }
+        if let Some(value) = line.strip_prefix("tags:") {
+            let value = value.trim();
+            in_tags = value.is_empty();
+            tags.extend(tag_values(
+                value.trim_start_matches('[').trim_end_matches(']'),
+            ));
+        }
+    }
+    Some((body.len() + 2, tags))
+}
+
+fn tag_values(value: &str) -> impl Iterator<Item = String> + '_ {
+    value
+        .split([',', ' '])
+        .map(|t| t.trim().trim_matches(['"', '\'']).trim_start_matches('#'))
+        .filter(|t| !t.is_empty())
+        .map(str::to_owned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(data: &str) -> Vec<Card> {
+        Markdown {}
+            .parse(Path::new("notes.md"), data, &Syntax::default())
+            .unwrap()
+    }
+
+    fn sorted(tags: &HashSet<String>) -> Vec<String> {
+        let mut tags: Vec<_> = tags.iter().cloned().collect();
+        tags.sort();
+        tags
+    }
+
+    #[test]
+    fn test_front_matter_inline_tags() {
+        let cards = parse(
+            "---\ntitle: Rust\ntags: [rust, \"#lang\"]\n---\nWhat is Send? : Movable across threads 🧠 #traits\n",
+        );
+        assert_eq!(cards.len(), 1);
+        assert_eq!(cards[0].line, 4);
+        assert_eq!(sorted(&cards[0].tags), vec!["lang", "rust", "traits"]);
+    }
+
+    #[test]
+    fn test_front_matter_block_tags() {
+        let cards =
+            parse("---\ntags:\n  - rust\n  - memory\naliases: []\n---\nQ #flashcard\nA\n---\n");
+        assert_eq!(cards.len(), 1);
+        assert_eq!(sorted(&cards[0].tags), vec!["memory", "rust"]);
+    }
+
+    #[test]
+    fn test_unclosed_front_matter_is_ignored() {
+        assert!(front_matter("---\ntags: [a]\nQ : A 🧠\n").is_none());
+        assert!(front_matter("Q : A 🧠\n---\n").is_none());
+        let cards = parse("---\nQ : A 🧠 #x\n");
+        assert_eq!(sorted(&cards[0].tags), vec!["x"]);
+    }
+}
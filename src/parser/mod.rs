@@ -0,0 +1,308 @@
+mod markdown;
+mod org;
+mod text;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use crate::card::{self, Card, Syntax};
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ParserKind {
+    Markdown,
+    Org,
+    Text,
+}
+
+pub trait CardParser {
+    fn parse(&self, file: &Path, contents: &str, syntax: &Syntax) -> Result<Vec<Card>>;
+    fn name(&self) -> &'static str;
+}
+
+pub fn new_parser(kind: ParserKind) -> Box<dyn CardParser> {
+    match kind {
+        ParserKind::Markdown => Box::new(markdown::Markdown {}),
+        ParserKind::Org => Box::new(org::Org {}),
+        ParserKind::Text => Box::new(text::Text {}),
+    }
+}
+
+const BUILTIN_EXTENSIONS: &[(&str, ParserKind)] = &[
+    ("md", ParserKind::Markdown),
+    ("org", ParserKind::Org),
+    ("txt", ParserKind::Text),
+];
+
+/// Maps file extensions onto the parser used to read cards from them.
+pub struct ParserRegistry {
+    parsers: HashMap<String, Box<dyn CardParser>>,
+    syntax: Syntax,
+}
+
+impl ParserRegistry {
+    /// Register a parser for every extension in `file_types` and `extra`.
+    /// Entries in `extra` override the built-in mapping; extensions that are
+    /// neither built in nor mapped are read as plain text.
+    pub fn new(file_types: &[String], extra: &HashMap<String, ParserKind>, syntax: Syntax) -> Self {
+        let builtin: HashMap<&str, ParserKind> = BUILTIN_EXTENSIONS.iter().copied().collect();
+        let parsers = file_types
+            .iter()
+            .map(|ext| {
+                let kind = builtin
+                    .get(ext.as_str())
+                    .copied()
+                    .unwrap_or(ParserKind::Text);
+                (ext.clone(), kind)
+            })
+            .chain(extra.iter().map(|(ext, kind)| (ext.clone(), *kind)))
+            .map(|(ext, kind)| (ext, new_parser(kind)))
+            .collect();
+        Self { parsers, syntax }
+    }
+
+    pub fn parser_for(&self, file: &Path) -> Option<&dyn CardParser> {
+        file.extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(|ext| self.parsers.get(ext))
+            .map(|p| p.as_ref())
+    }
+
+    pub fn handles(&self, file: &Path) -> bool {
+        self.parser_for(file).is_some()
+    }
+
+    /// Read and parse `file`, falling back to the plain text parser for
+    /// extensions without a registered parser.
+    pub fn parse_file(&self, file: &Path) -> Result<Vec<Card>> {
+        match read_card_file(file)? {
+            Some(contents) => self.parse_str(file, &contents),
+            None => Ok(vec![]),
+        }
+    }
+
+    fn parse_str(&self, file: &Path, contents: &str) -> Result<Vec<Card>> {
+        let contents = mask_ignored_regions(contents);
+        let fallback = text::Text {};
+        let parser = self.parser_for(file).unwrap_or(&fallback);
+        log::debug!("parsing {} as {}", file.display(), parser.name());
+        parser.parse(file, &contents, &self.syntax)
+    }
+
+    /// Write an identity anchor onto every card line in `file` that lacks one.
+    ///
+    /// Returns the `(old, new)` id of every card that was re-keyed; nothing is
+    /// written when `dry_run` is set.
+    pub fn assign_anchors(
+        &self,
+        file: &Path,
+        taken: &mut HashSet<String>,
+        dry_run: bool,
+    ) -> Result<Vec<(blake3::Hash, blake3::Hash)>> {
+        let Some(contents) = read_card_file(file)? else {
+            return Ok(vec![]);
+        };
+        let cards = self.parse_str(file, &contents)?;
+        let Some(updated) = card::write_anchors(&contents, &cards, taken)? else {
+            return Ok(vec![]);
+        };
+        let new_cards = self.parse_str(file, &updated)?;
+        let mapping = cards
+            .iter()
+            .zip(new_cards.iter())
+            .filter(|(old, new)| old.id != new.id)
+            .map(|(old, new)| (old.id, new.id))
+            .collect();
+        if !dry_run {
+            fs::write(file, updated)
+                .with_context(|| format!("Error writing `{}`", file.display()))?;
+        }
+        Ok(mapping)
+    }
+
+    /// Identity anchors already written in `file`.
+    pub fn anchors(&self, file: &Path) -> Result<Vec<String>> {
+        Ok(read_card_file(file)?
+            .map(|contents| card::anchors(&contents))
+            .unwrap_or_default())
+    }
+}
+
+fn read_card_file(file: &Path) -> Result<Option<String>> {
+    let contents =
+        fs::read_to_string(file).with_context(|| format!("Error reading `{}`", file.display()))?;
+    if contents.contains("@carddown-ignore") {
+        log::debug!("ignoring file: {}", file.display());
+        return Ok(None);
+    }
+    Ok(Some(contents))
+}
+
+const IGNORE_START: &str = "carddown-ignore-start";
+const IGNORE_END: &str = "carddown-ignore-end";
+
+/// Blank out every line from a `carddown-ignore-start` marker up to and
+/// including the matching `carddown-ignore-end` (or the end of the file),
+/// keeping line numbers intact.
+fn mask_ignored_regions(contents: &str) -> Cow<'_, str> {
+    if !contents.contains(IGNORE_START) {
+        return Cow::Borrowed(contents);
+    }
+    let mut ignoring = false;
+    let lines: Vec<&str> = contents
+        .lines()
+        .map(|line| {
+            if line.contains(IGNORE_START) {
+                ignoring = true;
+            }
+            let masked = if ignoring { "" } else { line };
+            if line.contains(IGNORE_END) {
+                ignoring = false;
+            }
+            masked
+        })
+        .collect();
+    Cow::Owned(lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::{self, NamedTempFile};
+
+    fn new_file(suffix: &str) -> Result<NamedTempFile> {
+        tempfile::Builder::new()
+            .suffix(suffix)
+            .tempfile()
+            .context("error creating tempfile")
+    }
+
+    fn default_registry() -> ParserRegistry {
+        let file_types = vec!["md".to_string(), "txt".to_string(), "org".to_string()];
+        ParserRegistry::new(&file_types, &HashMap::new(), Syntax::default())
+    }
+
+    #[test]
+    fn test_builtin_parsers() {
+        let registry = default_registry();
+        let name = |f: &str| registry.parser_for(Path::new(f)).map(|p| p.name());
+        assert_eq!(name("notes.md"), Some("markdown"));
+        assert_eq!(name("notes.org"), Some("org"));
+        assert_eq!(name("notes.txt"), Some("text"));
+        assert_eq!(name("notes.mdx"), None);
+        assert_eq!(name("README"), None);
+    }
+
+    #[test]
+    fn test_extra_extensions() {
+        let file_types = vec!["md".to_string(), "rst".to_string()];
+        let extra = HashMap::from([
+            ("mdx".to_string(), ParserKind::Markdown),
+            ("md".to_string(), ParserKind::Text),
+        ]);
+        let registry = ParserRegistry::new(&file_types, &extra, Syntax::default());
+        let name = |f: &str| registry.parser_for(Path::new(f)).map(|p| p.name());
+        assert_eq!(name("notes.mdx"), Some("markdown"));
+        assert_eq!(name("notes.md"), Some("text"));
+        assert_eq!(name("notes.rst"), Some("text"));
+        assert!(!registry.handles(Path::new("notes.org")));
+    }
+
+    #[test]
+    fn test_parse_file_with_ignore() {
+        let file = new_file(".md").unwrap();
+        let data = "@carddown-ignore\nWhat is the answer to life, the universe, and everything?: 42 #flashcard #foo #test";
+        fs::write(file.path(), data).unwrap();
+        let cards = default_registry().parse_file(file.path()).unwrap();
+        assert!(cards.is_empty());
+    }
+
+    #[test]
+    fn test_parse_file_with_ignore_regions() {
+        let file = new_file(".md").unwrap();
+        let data = "Q1 : A1 🧠\n\
+                    <!-- carddown-ignore-start -->\n\
+                    Q2 : A2 🧠\n\
+                    Draft #flashcard\n\
+                    <!-- carddown-ignore-end -->\n\
+                    Q3 : A3 🧠\n\
+                    <!-- carddown-ignore-start -->\n\
+                    Q4 : A4 🧠\n";
+        fs::write(file.path(), data).unwrap();
+        let cards = default_registry().parse_file(file.path()).unwrap();
+        let prompts: Vec<_> = cards.iter().map(|c| (c.prompt.as_str(), c.line)).collect();
+        assert_eq!(prompts, vec![("Q1", 0), ("Q3", 5)]);
+    }
+
+    #[test]
+    fn test_parse_file_unregistered_extension() {
+        let file = new_file(".rst").unwrap();
+        fs::write(file.path(), "Q: A 🧠").unwrap();
+        let cards = default_registry().parse_file(file.path()).unwrap();
+        assert_eq!(cards.len(), 1);
+    }
+
+    #[test]
+    fn test_assign_anchors() {
+        let file = new_file(".md").unwrap();
+        let data =
+            "Q1: A1 🧠\nQ2 #flashcard\nA2\n---\nThe {{c1::a}} {{c2::b}} 🧠\nQ3: A3 🧠 ^cd-aaaaaa\n";
+        fs::write(file.path(), data).unwrap();
+        let registry = default_registry();
+        let before = registry.parse_file(file.path()).unwrap();
+
+        let mut taken = HashSet::new();
+        let dry = registry
+            .assign_anchors(file.path(), &mut taken, true)
+            .unwrap();
+        assert_eq!(dry.len(), 4);
+        assert_eq!(fs::read_to_string(file.path()).unwrap(), data);
+
+        let mut taken = HashSet::new();
+        let mapping = registry
+            .assign_anchors(file.path(), &mut taken, false)
+            .unwrap();
+        assert_eq!(mapping.len(), 4);
+        let after = registry.parse_file(file.path()).unwrap();
+        assert_eq!(before.len(), after.len());
+        for ((old, new), (b, a)) in mapping.iter().zip(before.iter().zip(after.iter())) {
+            assert_eq!((old, new), (&b.id, &a.id));
+        }
+
+        // Running again is a no-op
+        assert!(registry
+            .assign_anchors(file.path(), &mut taken, false)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_assign_anchors_org_heading() {
+        let file = new_file(".org").unwrap();
+        fs::write(
+            file.path(),
+            "* Geography :europe:\n** Capital of France? :drill:\nParis\n",
+        )
+        .unwrap();
+        let registry = default_registry();
+        let before = registry.parse_file(file.path()).unwrap();
+        let mut taken = HashSet::new();
+        let mapping = registry
+            .assign_anchors(file.path(), &mut taken, false)
+            .unwrap();
+        assert_eq!(mapping.len(), 1);
+        // Org only reads tags at the very end of the headline
+        let contents = fs::read_to_string(file.path()).unwrap();
+        let headline = contents.lines().nth(1).unwrap();
+        assert!(headline.starts_with("** Capital of France? ^cd-"));
+        assert!(headline.ends_with(" :drill:"));
+        let after = registry.parse_file(file.path()).unwrap();
+        assert_eq!(before[0].prompt, after[0].prompt);
+        assert_eq!(after[0].tags, before[0].tags);
+        assert_eq!(mapping[0], (before[0].id, after[0].id));
+    }
+}
@@ -0,0 +1,475 @@
+use anyhow::Result;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use super::CardParser;
+use crate::card::{self, Card, CardKind, Syntax};
+
+static HEADING_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(\*+)\s+(.*?)(?:\s+(:(?:[\w@#%]+:)+))?\s*$").unwrap());
+static FILETAGS_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)^\s*#\+FILETAGS:\s*(.*?)\s*$").unwrap());
+static DRAWER_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\s*:([\w-]+):\s*$").unwrap());
+static DRAWER_END_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)^\s*:END:\s*$").unwrap());
+static PROPERTY_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*:([\w-]+\+?):\s*(.*?)\s*$").unwrap());
+static PLANNING_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*(SCHEDULED|DEADLINE|CLOSED):").unwrap());
+
+/// Heading tags that turn a subtree into a card.
+const CARD_TAGS: &[&str] = &["drill", "flashcard"];
+
+/// Reads org-drill style subtree cards (headings tagged `:drill:` or
+/// `:flashcard:`) as well as the line based card syntax.
+pub struct Org {}
+
+impl CardParser for Org {
+    fn parse(&self, file: &Path, contents: &str, syntax: &Syntax) -> Result<Vec<Card>> {
+        let lines: Vec<&str> = contents.lines().collect();
+        let file_tags: HashSet<String> = lines
+            .iter()
+            .filter_map(|l| FILETAGS_RE.captures(l))
+            .filter_map(|caps| caps.get(1))
+            .flat_map(|m| split_tags(m.as_str()))
+            .collect();
+
+        let mut cards = vec![];
+        // Subtree cards are blanked out before the line based parser runs so
+        // their bodies aren't picked up a second time.
+        let mut rest: Vec<&str> = lines.clone();
+        let mut ancestors: Vec<Heading> = vec![];
+        let mut i = 0;
+        while i < lines.len() {
+            let Some(heading) = Heading::parse(lines[i]) else {
+                i += 1;
+                continue;
+            };
+            ancestors.retain(|a| a.level < heading.level);
+            if !heading.tags.iter().any(|t| CARD_TAGS.contains(&t.as_str())) {
+                ancestors.push(heading);
+                i += 1;
+                continue;
+            }
+            let end = subtree_end(&lines, i + 1, heading.level);
+            let tags = file_tags
+                .iter()
+                .chain(ancestors.iter().flat_map(|a| a.tags.iter()))
+                .chain(heading.tags.iter())
+                .filter(|t| !CARD_TAGS.contains(&t.as_str()))
+                .cloned()
+                .collect();
+            let breadcrumb = ancestors.iter().map(|a| a.title.clone()).collect();
+            cards.extend(subtree_cards(
+                file,
+                i,
+                &heading,
+                &lines[i + 1..end],
+                tags,
+                breadcrumb,
+            ));
+            rest[i..end].fill("");
+            i = end;
+        }
+
+        // `# ...` lines are comments in org, not markdown headings
+        let syntax = syntax.clone().with_markdown_headings(false);
+        let mut line_cards = card::parse_lines(file, &rest.join("\n"), &syntax)?;
+        for card in &mut line_cards {
+            card.tags.extend(file_tags.iter().cloned());
+        }
+        set_breadcrumbs(&lines, &mut line_cards);
+        cards.extend(line_cards);
+        cards.sort_by_key(|c| c.line);
+        Ok(cards)
+    }
+    fn name(&self) -> &'static str {
+        "org"
+    }
+}
+
+struct Heading {
+    level: usize,
+    title: String,
+    tags: Vec<String>,
+    anchor: Option<String>,
+}
+
+impl Heading {
+    fn parse(line: &str) -> Option<Self> {
+        let (line, anchor) = card::take_anchor(line);
+        let caps = HEADING_RE.captures(&line)?;
+        Some(Self {
+            level: caps.get(1)?.as_str().len(),
+            title: caps.get(2)?.as_str().trim().to_string(),
+            tags: caps
+                .get(3)
+                .map(|m| split_tags(m.as_str()).collect())
+                .unwrap_or_default(),
+            anchor,
+        })
+    }
+}
+
+/// A child heading of a multi-sided card and the text below it.
+struct Side {
+    title: String,
+    text: String,
+}
+
+fn split_tags(tags: &str) -> impl Iterator<Item = String> + '_ {
+    tags.split(|c: char| c == ':' || c.is_whitespace())
+        .filter(|t| !t.is_empty())
+        .map(str::to_owned)
+}
+
+/// Index of the first line at or after `start` that begins a heading of
+/// `level` or higher.
+fn subtree_end(lines: &[&str], start: usize, level: usize) -> usize {
+    lines[start..]
+        .iter()
+        .position(|l| Heading::parse(l).is_some_and(|h| h.level <= level))
+        .map_or(lines.len(), |p| start + p)
+}
+
+/// Set the breadcrumb of each of `cards` to the titles of the headings
+/// enclosing its line, outermost first, in one pass over `lines`.
+fn set_breadcrumbs(lines: &[&str], cards: &mut [Card]) {
+    cards.sort_by_key(|c| c.line);
+    let mut ancestors: Vec<Heading> = vec![];
+    let mut read = 0;
+    for card in cards {
+        let line = card.line as usize;
+        for l in &lines[read..line] {
+            if let Some(heading) = Heading::parse(l) {
+                ancestors.retain(|a| a.level < heading.level);
+                ancestors.push(heading);
+            }
+        }
+        read = line;
+        // A card written on a heading line is not inside that heading
+        let level = lines
+            .get(line)
+            .and_then(|l| Heading::parse(l))
+            .map(|h| h.level);
+        card.breadcrumb = ancestors
+            .iter()
+            .filter(|a| level.is_none_or(|level| a.level < level))
+            .map(|a| a.title.clone())
+            .collect();
+    }
+}
+
+/// Split the `:PROPERTIES:` drawer off `body` and drop other drawers and
+/// planning lines.
+fn read_body<'a>(body: &[&'a str]) -> (HashMap<String, String>, Vec<&'a str>) {
+    let mut properties = HashMap::new();
+    let mut lines = vec![];
+    let mut drawer: Option<String> = None;
+    for line in body {
+        if let Some(name) = &drawer {
+            if DRAWER_END_RE.is_match(line) {
+                drawer = None;
+            } else if name.eq_ignore_ascii_case("PROPERTIES") {
+                if let Some(caps) = PROPERTY_RE.captures(line) {
+                    properties.insert(caps[1].to_uppercase(), caps[2].to_string());
+                }
+            }
+        } else if let Some(caps) = DRAWER_RE.captures(line) {
+            drawer = Some(caps[1].to_string());
+        } else if !PLANNING_RE.is_match(line) {
+            lines.push(*line);
+        }
+    }
+    (properties, lines)
+}
+
+/// Strip blank lines from both ends of `lines` and remove their common
+/// indentation.
+fn dedent(lines: &[&str]) -> Vec<String> {
+    let start = lines.iter().position(|l| !l.trim().is_empty());
+    let end = lines.iter().rposition(|l| !l.trim().is_empty());
+    let (Some(start), Some(end)) = (start, end) else {
+        return vec![];
+    };
+    let lines = &lines[start..=end];
+    let indent = lines
+        .iter()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| l.len() - l.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    lines
+        .iter()
+        .map(|l| l.get(indent..).unwrap_or("").trim_end().to_string())
+        .collect()
+}
+
+fn sides(lines: &[&str]) -> Vec<Side> {
+    let Some(level) = lines
+        .iter()
+        .filter_map(|l| Heading::parse(l))
+        .map(|h| h.level)
+        .min()
+    else {
+        return vec![];
+    };
+    let mut sides = vec![];
+    for (i, line) in lines.iter().enumerate() {
+        let Some(heading) = Heading::parse(line).filter(|h| h.level == level) else {
+            continue;
+        };
+        let end = subtree_end(lines, i + 1, level);
+        let text = dedent(&lines[i + 1..end]).join(" ");
+        sides.push(Side {
+            title: heading.title,
+            text,
+        });
+    }
+    sides
+}
+
+fn subtree_cards(
+    file: &Path,
+    line: usize,
+    heading: &Heading,
+    body: &[&str],
+    tags: HashSet<String>,
+    breadcrumb: Vec<String>,
+) -> Vec<Card> {
+    let (properties, body) = read_body(body);
+    let card_type = properties
+        .get("DRILL_CARD_TYPE")
+        .map(|t| t.to_lowercase())
+        .unwrap_or_else(|| "simple".to_string());
+    let new_card = |id: blake3::Hash, prompt: String, response: Vec<String>| Card {
+        id,
+        file: PathBuf::from(file),
+        line: line as u64,
+        prompt,
+        response,
+        tags: tags.clone(),
+        kind: CardKind::Basic,
+        breadcrumb: breadcrumb.clone(),
+        hints: vec![],
+        directives: Default::default(),
+    };
+
+    let sides = sides(&body);
+    let pairs: Vec<(usize, Vec<usize>)> = match card_type.as_str() {
+        "twosided" if sides.len() >= 2 => vec![(0, vec![1]), (1, vec![0])],
+        "multisided" if sides.len() >= 2 => (0..sides.len())
+            .map(|i| (i, (0..sides.len()).filter(|j| *j != i).collect()))
+            .collect(),
+        "simple" => vec![],
+        other => {
+            log::warn!(
+                "{}:{}: unsupported DRILL_CARD_TYPE `{other}`, reading as a simple card",
+                file.display(),
+                line + 1
+            );
+            vec![]
+        }
+    };
+
+    if pairs.is_empty() {
+        let response: Vec<String> = dedent(&body)
+            .into_iter()
+            .map(|l| match Heading::parse(&l) {
+                Some(h) => h.title,
+                None => l,
+            })
+            .collect();
+        let id = match &heading.anchor {
+            Some(anchor) => card::anchor_id(anchor),
+            None => blake3::hash(format!("{}\n{}", heading.title, response.join("\n")).as_bytes()),
+        };
+        return vec![new_card(id, heading.title.clone(), response)];
+    }
+
+    pairs
+        .into_iter()
+        .map(|(front, back)| {
+            let side = |i: usize| format!("{}: {}", sides[i].title, sides[i].text);
+            let prompt = format!("{} ({})", heading.title, side(front));
+            let response: Vec<String> = back.into_iter().map(side).collect();
+            let id = match &heading.anchor {
+                Some(anchor) => blake3::hash(format!("cd:{anchor}\0side{front}").as_bytes()),
+                None => blake3::hash(format!("{prompt}\n{}", response.join("\n")).as_bytes()),
+            };
+            new_card(id, prompt, response)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(data: &str) -> Vec<Card> {
+        Org {}
+            .parse(Path::new("notes.org"), data, &Syntax::default())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_simple_drill_card() {
+        let cards = parse(
+            "#+FILETAGS: :geo:europe:\n\
+             * Countries :travel:\n\
+             ** Capital of France? :drill:\n\
+             :PROPERTIES:\n\
+             :ID: 1234\n\
+             :END:\n\
+             SCHEDULED: <2024-01-01 Mon>\n\
+             \n\
+             \x20 Paris\n\
+             \x20 (on the Seine)\n\
+             ** Not a card\n\
+             Nothing here\n",
+        );
+        assert_eq!(cards.len(), 1);
+        let card = &cards[0];
+        assert_eq!(card.line, 2);
+        assert_eq!(card.prompt, "Capital of France?");
+        assert_eq!(card.response, vec!["Paris", "(on the Seine)"]);
+        assert_eq!(
+            card.tags,
+            HashSet::from(["geo".into(), "europe".into(), "travel".into()])
+        );
+    }
+
+    #[test]
+    fn test_breadcrumb() {
+        let cards = parse(
+            "* Geography\n\
+             ** Europe :travel:\n\
+             *** Capital of France? :drill:\n\
+             Paris\n\
+             *** Capital of Spain? : Madrid 🧠\n\
+             * Top level : card 🧠\n",
+        );
+        assert_eq!(cards.len(), 3);
+        assert_eq!(cards[0].breadcrumb, vec!["Geography", "Europe"]);
+        assert_eq!(cards[1].breadcrumb, vec!["Geography", "Europe"]);
+        assert!(cards[2].breadcrumb.is_empty());
+    }
+
+    #[test]
+    fn test_comments_are_not_headings() {
+        let cards = parse(
+            "* Rust\n\
+             What is a move? #flashcard\n\
+             # a comment, not a heading\n\
+             Ownership transfer\n\
+             ---\n\
+             # Borrowing #flashcard\n\
+             ** Lifetimes\n\
+             What is 'static? : Whole program 🧠\n",
+        );
+        // `# Borrowing #flashcard` is an unclosed multi-line card, not a
+        // heading card
+        assert_eq!(cards.len(), 2);
+        assert_eq!(cards[0].breadcrumb, vec!["Rust"]);
+        assert_eq!(
+            cards[0].response,
+            vec!["# a comment, not a heading", "Ownership transfer"]
+        );
+        assert_eq!(cards[1].breadcrumb, vec!["Rust", "Lifetimes"]);
+    }
+
+    #[test]
+    fn test_flashcard_tag_and_subheadings() {
+        let cards = parse(
+            "* Ownership :flashcard:rust:\n\
+             Who frees the memory?\n\
+             ** Answer\n\
+             The owner\n\
+             * Next\n",
+        );
+        assert_eq!(cards.len(), 1);
+        assert_eq!(cards[0].prompt, "Ownership");
+        assert_eq!(
+            cards[0].response,
+            vec!["Who frees the memory?", "Answer", "The owner"]
+        );
+        assert_eq!(cards[0].tags, HashSet::from(["rust".into()]));
+    }
+
+    #[test]
+    fn test_twosided_card() {
+        let cards = parse(
+            "* Noun :drill:\n\
+             :PROPERTIES:\n\
+             :DRILL_CARD_TYPE: twosided\n\
+             :END:\n\
+             ** Spanish\n\
+             la casa\n\
+             ** English\n\
+             the house\n",
+        );
+        assert_eq!(cards.len(), 2);
+        assert_eq!(cards[0].prompt, "Noun (Spanish: la casa)");
+        assert_eq!(cards[0].response, vec!["English: the house"]);
+        assert_eq!(cards[1].prompt, "Noun (English: the house)");
+        assert_eq!(cards[1].response, vec!["Spanish: la casa"]);
+        assert_ne!(cards[0].id, cards[1].id);
+    }
+
+    #[test]
+    fn test_multisided_card() {
+        let cards = parse(
+            "* Verb :drill:\n\
+             :PROPERTIES:\n\
+             :DRILL_CARD_TYPE: multisided\n\
+             :END:\n\
+             ** Spanish\nir\n** English\nto go\n** French\naller\n",
+        );
+        assert_eq!(cards.len(), 3);
+        assert_eq!(cards[2].prompt, "Verb (French: aller)");
+        assert_eq!(cards[2].response, vec!["Spanish: ir", "English: to go"]);
+    }
+
+    #[test]
+    fn test_unknown_card_type_reads_as_simple() {
+        let cards = parse("* Q :drill:\n:PROPERTIES:\n:DRILL_CARD_TYPE: hide1cloze\n:END:\nA\n");
+        assert_eq!(cards.len(), 1);
+        assert_eq!(cards[0].response, vec!["A"]);
+    }
+
+    #[test]
+    fn test_filetags_apply_to_line_cards() {
+        let cards = parse("#+FILETAGS: :geo:\nCapital of Spain? : Madrid 🧠 #europe\n");
+        assert_eq!(
+            cards[0].tags,
+            HashSet::from(["geo".into(), "europe".into()])
+        );
+    }
+
+    #[test]
+    fn test_line_cards_still_parsed() {
+        let cards = parse(
+            "* Notes\n\
+             Capital of Spain? : Madrid 🧠\n\
+             * Q :drill:\n\
+             Capital of Italy? : Rome 🧠\n",
+        );
+        assert_eq!(cards.len(), 2);
+        assert_eq!(cards[0].prompt, "Capital of Spain?");
+        assert_eq!(cards[1].prompt, "Q");
+        assert_eq!(cards[1].response, vec!["Capital of Italy? : Rome 🧠"]);
+    }
+
+    #[test]
+    fn test_anchored_heading() {
+        let before = parse("* Q :drill: ^cd-abcdef\nA\n");
+        let after = parse("* Q edited :drill: ^cd-abcdef\nA\n");
+        assert_eq!(before[0].id, after[0].id);
+        assert_eq!(after[0].prompt, "Q edited");
+        // As written by `scan --assign-ids`, before the tag block
+        let written = parse("* Q ^cd-abcdef :drill:\nA\n");
+        assert_eq!(written[0].id, before[0].id);
+        assert_eq!(written[0].prompt, "Q");
+    }
+}
@@ -0,0 +1,16 @@
+use anyhow::Result;
+use std::path::Path;
+
+use super::CardParser;
+use crate::card::{self, Card, Syntax};
+
+pub struct Text {}
+
+impl CardParser for Text {
+    fn parse(&self, file: &Path, contents: &str, syntax: &Syntax) -> Result<Vec<Card>> {
+        card::parse_lines(file, contents, syntax)
+    }
+    fn name(&self) -> &'static str {
+        "text"
+    }
+}
@@ -0,0 +1,134 @@
+/// Levenshtein edit distance between two strings, counted in chars.
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.is_empty() {
+        return b.len();
+    }
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        curr[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != cb);
+            curr[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+    prev[b.len()]
+}
+
+/// Normalised similarity in `[0.0, 1.0]`, where 1.0 means identical.
+pub fn similarity(a: &str, b: &str) -> f64 {
+    let len = a.chars().count().max(b.chars().count());
+    if len == 0 {
+        return 1.0;
+    }
+    1.0 - levenshtein(a, b) as f64 / len as f64
+}
+
+/// Normalise a typed answer for comparison: lowercase, whitespace removed.
+pub fn normalise_answer(answer: &str) -> String {
+    answer
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// A run of characters in a [`diff`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Diff {
+    Same(String),
+    /// Only in the first string.
+    Removed(String),
+    /// Only in the second string.
+    Added(String),
+}
+
+/// Character diff turning `from` into `to`, comparing case-insensitively.
+pub fn diff(from: &str, to: &str) -> Vec<Diff> {
+    let a: Vec<char> = from.chars().collect();
+    let b: Vec<char> = to.chars().collect();
+    let same = |x: char, y: char| x.to_lowercase().eq(y.to_lowercase());
+    // lcs[i][j]: longest common subsequence of a[i..] and b[j..]
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if same(a[i], b[j]) {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let mut out: Vec<Diff> = vec![];
+    let mut push = |d: Diff| match (out.last_mut(), d) {
+        (Some(Diff::Same(s)), Diff::Same(c))
+        | (Some(Diff::Removed(s)), Diff::Removed(c))
+        | (Some(Diff::Added(s)), Diff::Added(c)) => s.push_str(&c),
+        (_, d) => out.push(d),
+    };
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && same(a[i], b[j]) {
+            push(Diff::Same(a[i].to_string()));
+            i += 1;
+            j += 1;
+        } else if j < b.len() && (i == a.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            push(Diff::Added(b[j].to_string()));
+            j += 1;
+        } else {
+            push(Diff::Removed(a[i].to_string()));
+            i += 1;
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_levenshtein() {
+        assert_eq!(levenshtein("", ""), 0);
+        assert_eq!(levenshtein("abc", ""), 3);
+        assert_eq!(levenshtein("", "abc"), 3);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("flaw", "lawn"), 2);
+        assert_eq!(levenshtein("¿Cómo?", "Como?"), 2);
+    }
+
+    #[test]
+    fn test_similarity() {
+        assert_eq!(similarity("", ""), 1.0);
+        assert_eq!(similarity("abc", "abc"), 1.0);
+        assert_eq!(similarity("abc", "xyz"), 0.0);
+        assert!(similarity("Capital of France", "Capital of Frence") > 0.9);
+    }
+
+    #[test]
+    fn test_normalise_answer() {
+        assert_eq!(normalise_answer("  Ice\tCream "), "icecream");
+        assert_eq!(normalise_answer("ÉCOLE"), "école");
+    }
+
+    #[test]
+    fn test_diff() {
+        assert_eq!(diff("", ""), vec![]);
+        assert_eq!(diff("Paris", "paris"), vec![Diff::Same("Paris".into())]);
+        assert_eq!(
+            diff("recieve", "receive"),
+            vec![
+                Diff::Same("rec".into()),
+                Diff::Added("e".into()),
+                Diff::Same("i".into()),
+                Diff::Removed("e".into()),
+                Diff::Same("ve".into()),
+            ]
+        );
+        assert_eq!(diff("", "ab"), vec![Diff::Added("ab".into())]);
+        assert_eq!(diff("ab", ""), vec![Diff::Removed("ab".into())]);
+    }
+}
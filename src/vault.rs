@@ -2,6 +2,8 @@ use std::path::{Path, PathBuf};
 
 const PROJECT_MARKERS: &[&str] = &[".carddown", ".git", ".hg", ".jj"];
 const VAULT_DIR: &str = ".carddown";
+/// Gitignore-style patterns for paths `scan` should skip, at the vault root.
+pub const IGNORE_FILE: &str = ".carddownignore";
 
 /// Resolved vault paths for vault-local config and persistent state files.
 pub struct VaultPaths {
@@ -1,4 +1,5 @@
 use crate::view::formatting::{format_datetime, format_datetime_opt, format_tags};
+use crate::view::markdown::{self, render_markdown};
 use anyhow::Result;
 use ratatui::prelude::*;
 use std::io;
@@ -74,44 +75,40 @@ impl App {
     fn handle_key_event(&mut self, key_event: KeyEvent) {
         match key_event.code {
             KeyCode::Char('q') | KeyCode::Char('Q') => self.exit(),
-            KeyCode::Char('d') | KeyCode::Char('D') => {
-                if !self.cards.is_empty() {
-                    self.sure = true;
-                    self.status = None;
-                }
+            KeyCode::Char('d') | KeyCode::Char('D') if !self.cards.is_empty() => {
+                self.sure = true;
+                self.status = None;
             }
             KeyCode::Char('n') | KeyCode::Char('N') => {
                 self.sure = false;
                 self.status = None;
             }
-            KeyCode::Char('y') | KeyCode::Char('Y') => {
-                if self.sure && !self.cards.is_empty() {
-                    let card = self.cards.remove(self.current_card);
-                    if card.leech {
-                        log::warn!("Cannot delete leech card: {}", card.card.id);
-                        self.status = Some(StatusMessage {
-                            text: "Cannot delete leech cards".to_string(),
-                            kind: StatusKind::Error,
-                        });
-                        self.cards.insert(self.current_card, card);
-                    } else if let Err(e) = (self.delete_fn)(card.card.id) {
-                        log::error!("Failed to delete card {}: {e}", card.card.id);
-                        self.status = Some(StatusMessage {
-                            text: format!("Delete failed: {e}"),
-                            kind: StatusKind::Error,
-                        });
-                        self.cards.insert(self.current_card, card);
-                    } else {
-                        self.status = Some(StatusMessage {
-                            text: "Card deleted".to_string(),
-                            kind: StatusKind::Success,
-                        });
-                        if self.current_card >= self.cards.len() && !self.cards.is_empty() {
-                            self.current_card = self.cards.len() - 1;
-                        }
+            KeyCode::Char('y') | KeyCode::Char('Y') if self.sure && !self.cards.is_empty() => {
+                let card = self.cards.remove(self.current_card);
+                if card.leech {
+                    log::warn!("Cannot delete leech card: {}", card.card.id);
+                    self.status = Some(StatusMessage {
+                        text: "Cannot delete leech cards".to_string(),
+                        kind: StatusKind::Error,
+                    });
+                    self.cards.insert(self.current_card, card);
+                } else if let Err(e) = (self.delete_fn)(card.card.id) {
+                    log::error!("Failed to delete card {}: {e}", card.card.id);
+                    self.status = Some(StatusMessage {
+                        text: format!("Delete failed: {e}"),
+                        kind: StatusKind::Error,
+                    });
+                    self.cards.insert(self.current_card, card);
+                } else {
+                    self.status = Some(StatusMessage {
+                        text: "Card deleted".to_string(),
+                        kind: StatusKind::Success,
+                    });
+                    if self.current_card >= self.cards.len() && !self.cards.is_empty() {
+                        self.current_card = self.cards.len() - 1;
                     }
-                    self.sure = false;
                 }
+                self.sure = false;
             }
             KeyCode::Left | KeyCode::Char('h') | KeyCode::Char('k') => {
                 self.status = None;
@@ -212,12 +209,10 @@ impl App {
             });
             lines.push(Line::from(vec![]));
             lines.push(Line::from(vec!["Prompt".bold()]));
-            lines.push(Line::from(vec![card.card.prompt.clone().into()]));
+            lines.extend(render_markdown(&card.card.prompt));
             lines.push(Line::from(vec![]));
             lines.push(Line::from(vec!["Response".bold()]));
-            for l in card.card.response.iter() {
-                lines.push(Line::from(vec![l.into()]));
-            }
+            lines.extend(render_markdown(&card.card.response.join("\n")));
             lines.push(Line::from(vec![]));
             lines.push(Line::from(vec!["Tags".bold()]));
             lines.push(Line::from(vec![format_tags(&card.card.tags).into()]));
@@ -264,8 +259,8 @@ impl Widget for &App {
         } else {
             self.card_audit()
         };
-        Paragraph::new(counter_text)
-            .wrap(Wrap { trim: true })
+        let width = block.inner(area).width;
+        Paragraph::new(markdown::wrap(counter_text, width))
             .centered()
             .block(block)
             .render(area, buf);
@@ -276,6 +271,7 @@ impl Widget for &App {
 mod tests {
     use super::*;
     use crate::algorithm::CardState;
+    use crate::card::CardKind;
     use chrono::Utc;
     use std::collections::HashSet;
     use std::path::PathBuf;
@@ -287,10 +283,15 @@ mod tests {
                 prompt: "test prompt".to_string(),
                 response: vec!["test response".to_string()],
                 tags: HashSet::from_iter(vec!["test_tag".to_string()]),
+                kind: CardKind::Basic,
+                breadcrumb: vec![],
+                hints: vec![],
+                directives: Default::default(),
                 file: PathBuf::from("test/file.md"),
                 line: 1,
             },
             revise_count: 0,
+            hints_used: 0,
             last_revised: None,
             added: Utc::now(),
             orphan: false,
@@ -1,5 +1,5 @@
 use chrono::{DateTime, Local};
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 
 /// Format a DateTime as a string in local time
 pub fn format_datetime(dt: DateTime<chrono::Utc>) -> String {
@@ -13,16 +13,41 @@ pub fn format_datetime_opt(dt: Option<DateTime<chrono::Utc>>, fallback: &str) ->
         .unwrap_or_else(|| fallback.to_string())
 }
 
-/// Format a set of tags as a sorted, comma-separated string.
+/// Format a set of tags as a sorted, comma-separated string. Hierarchical
+/// tags sharing a parent are grouped, e.g. `lang › {python, rust › async}`.
 ///
 /// Returns an empty string if the set is empty.
 pub fn format_tags(tags: &HashSet<String>) -> String {
-    if tags.is_empty() {
-        return String::new();
+    let paths: Vec<Vec<&str>> = tags.iter().map(|t| t.split('/').collect()).collect();
+    format_tag_tree(paths.iter().map(Vec::as_slice).collect()).join(", ")
+}
+
+/// Format the headings enclosing a card, e.g. `Rust > Iterators > Option`.
+pub fn format_breadcrumb(breadcrumb: &[String]) -> String {
+    breadcrumb.join(" > ")
+}
+
+fn format_tag_tree(paths: Vec<&[&str]>) -> Vec<String> {
+    let mut children: BTreeMap<&str, Vec<&[&str]>> = BTreeMap::new();
+    for path in paths {
+        if let Some((root, rest)) = path.split_first() {
+            let entry = children.entry(root).or_default();
+            if !rest.is_empty() {
+                entry.push(rest);
+            }
+        }
     }
-    let mut sorted: Vec<_> = tags.iter().map(|s| s.as_str()).collect();
-    sorted.sort();
-    sorted.join(", ")
+    children
+        .into_iter()
+        .map(|(root, rest)| {
+            let sub = format_tag_tree(rest);
+            match sub.as_slice() {
+                [] => root.to_string(),
+                [only] => format!("{root} › {only}"),
+                _ => format!("{root} › {{{}}}", sub.join(", ")),
+            }
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -46,6 +71,21 @@ mod tests {
         assert_eq!(format_tags(&tags), "alpha, mid, zebra");
     }
 
+    #[test]
+    fn test_format_tags_hierarchy() {
+        let tags = HashSet::from([
+            "misc".to_string(),
+            "lang/rust/async".to_string(),
+            "lang/python".to_string(),
+            "lang".to_string(),
+            "db/sql".to_string(),
+        ]);
+        assert_eq!(
+            format_tags(&tags),
+            "db › sql, lang › {python, rust › async}, misc"
+        );
+    }
+
     #[test]
     fn test_format_datetime_opt_none() {
         assert_eq!(format_datetime_opt(None, "never"), "never");
@@ -58,4 +98,11 @@ mod tests {
         assert_ne!(result, "never");
         assert!(result.contains('-')); // date format contains dashes
     }
+
+    #[test]
+    fn test_format_breadcrumb() {
+        assert_eq!(format_breadcrumb(&[]), "");
+        let crumbs = ["Rust", "Iterators", "Option"].map(String::from);
+        assert_eq!(format_breadcrumb(&crumbs), "Rust > Iterators > Option");
+    }
 }
@@ -0,0 +1,178 @@
+use ratatui::prelude::*;
+use std::str::FromStr;
+use std::sync::LazyLock;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{
+    self, FontStyle, ScopeSelectors, StyleModifier, Theme, ThemeItem, ThemeSettings,
+};
+use syntect::parsing::SyntaxSet;
+use syntect::util::LinesWithEndings;
+
+static SYNTAXES: LazyLock<SyntaxSet> = LazyLock::new(SyntaxSet::load_defaults_newlines);
+static THEME: LazyLock<Theme> = LazyLock::new(ansi_theme);
+
+/// Marks a theme colour as an index into the terminal's own palette.
+const ANSI: u8 = 0;
+/// Marks the terminal's default foreground.
+const DEFAULT: u8 = 1;
+
+/// Theme scopes and the terminal palette colour (0-15) used for them. Sticking
+/// to the palette lets the terminal pick shades that work on dark and light
+/// backgrounds alike.
+const SCOPE_COLORS: &[(&str, u8)] = &[
+    ("comment", 8),
+    ("string, constant.character", 2),
+    ("constant.numeric, constant.language, constant.other", 1),
+    ("keyword, storage", 5),
+    (
+        "entity.name.function, support.function, variable.function",
+        4,
+    ),
+    (
+        "entity.name.type, entity.name.class, support.type, support.class",
+        6,
+    ),
+    ("entity.name.tag, entity.other.attribute-name", 4),
+    ("variable.parameter", 3),
+];
+
+/// Alternative fence names mapped onto a syntax name or extension.
+const LANGUAGE_ALIASES: &[(&str, &str)] = &[
+    ("shell", "sh"),
+    ("zsh", "sh"),
+    ("console", "sh"),
+    ("psql", "sql"),
+    ("postgres", "sql"),
+    ("postgresql", "sql"),
+    ("mysql", "sql"),
+    ("sqlite", "sql"),
+    ("py3", "py"),
+];
+
+fn ansi_color(index: u8) -> highlighting::Color {
+    highlighting::Color {
+        r: index,
+        g: 0,
+        b: 0,
+        a: ANSI,
+    }
+}
+
+fn ansi_theme() -> Theme {
+    let scopes = SCOPE_COLORS
+        .iter()
+        .map(|(scope, color)| ThemeItem {
+            scope: ScopeSelectors::from_str(scope).expect("valid scope selector"),
+            style: StyleModifier {
+                foreground: Some(ansi_color(*color)),
+                background: None,
+                font_style: scope.starts_with("comment").then_some(FontStyle::ITALIC),
+            },
+        })
+        .collect();
+    Theme {
+        name: Some("carddown-ansi".to_string()),
+        author: None,
+        settings: ThemeSettings {
+            foreground: Some(highlighting::Color {
+                r: 0,
+                g: 0,
+                b: 0,
+                a: DEFAULT,
+            }),
+            ..ThemeSettings::default()
+        },
+        scopes,
+    }
+}
+
+fn to_style(style: highlighting::Style) -> Style {
+    let mut out = Style::new();
+    let fg = style.foreground;
+    if fg.a == ANSI {
+        out = out.fg(Color::Indexed(fg.r));
+    }
+    if style.font_style.contains(FontStyle::BOLD) {
+        out = out.bold();
+    }
+    if style.font_style.contains(FontStyle::ITALIC) {
+        out = out.italic();
+    }
+    if style.font_style.contains(FontStyle::UNDERLINE) {
+        out = out.underlined();
+    }
+    out
+}
+
+/// Highlight `code` as `language` (a fence info string such as `rust` or
+/// `py`), returning the spans of each line. Returns `None` for unknown
+/// languages.
+pub fn highlight_code(code: &str, language: &str) -> Option<Vec<Vec<Span<'static>>>> {
+    let language = language.trim().to_lowercase();
+    let token = LANGUAGE_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == language)
+        .map_or(language.as_str(), |(_, name)| name);
+    let syntax = SYNTAXES.find_syntax_by_token(token)?;
+    let mut highlighter = HighlightLines::new(syntax, &THEME);
+    LinesWithEndings::from(code)
+        .map(|line| {
+            let ranges = highlighter.highlight_line(line, &SYNTAXES).ok()?;
+            Some(
+                ranges
+                    .into_iter()
+                    .map(|(style, text)| {
+                        let text = text.trim_end_matches(['\n', '\r']).replace('\t', "    ");
+                        Span::styled(text, to_style(style))
+                    })
+                    .filter(|span| !span.content.is_empty())
+                    .collect(),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn color_of(spans: &[Span], text: &str) -> Option<Color> {
+        spans
+            .iter()
+            .find(|s| s.content == text)
+            .and_then(|s| s.style.fg)
+    }
+
+    #[test]
+    fn test_highlight_rust() {
+        let lines = highlight_code("fn main() {\n\tlet x = \"hi\"; // greet\n}\n", "rust").unwrap();
+        assert_eq!(lines.len(), 3);
+        let text: Vec<String> = lines
+            .iter()
+            .map(|l| l.iter().map(|s| s.content.as_ref()).collect())
+            .collect();
+        assert_eq!(text[1], "    let x = \"hi\"; // greet");
+        assert_eq!(color_of(&lines[0], "fn"), Some(Color::Indexed(5)));
+        assert_eq!(color_of(&lines[0], "main"), Some(Color::Indexed(4)));
+        assert!(lines[1]
+            .iter()
+            .any(|s| s.content.contains("greet") && s.style.fg == Some(Color::Indexed(8))));
+    }
+
+    #[test]
+    fn test_supported_languages() {
+        for lang in [
+            "rust", "rs", "python", "py", "sql", "sh", "bash", "shell", "zsh",
+        ] {
+            assert!(highlight_code("x", lang).is_some(), "{lang}");
+        }
+        assert!(highlight_code("x", "not-a-language").is_none());
+    }
+
+    #[test]
+    fn test_plain_text_keeps_default_colour() {
+        let lines = highlight_code("SELECT 1 FROM t", "SQL").unwrap();
+        assert_eq!(color_of(&lines[0], "SELECT"), Some(Color::Indexed(5)));
+        assert!(lines[0].iter().any(|s| s.style.fg.is_none()));
+    }
+}
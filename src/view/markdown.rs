@@ -0,0 +1,439 @@
+use pulldown_cmark::{CodeBlockKind, Event, Options, Parser, Tag, TagEnd};
+use ratatui::prelude::*;
+
+use super::highlight::highlight_code;
+
+/// Render markdown `text` as styled lines.
+///
+/// Emphasis, inline code, headings, lists and block quotes are styled; every
+/// source line break is kept. Code blocks are left aligned (see [`wrap`]) and
+/// syntax highlighted when their language is known.
+pub fn render_markdown(text: &str) -> Vec<Line<'static>> {
+    let mut renderer = Renderer::default();
+    let options = Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    for event in Parser::new_ext(text, options) {
+        renderer.event(event);
+    }
+    renderer.flush_line();
+    renderer.lines
+}
+
+/// Word-wrap every line of `text` to `width` columns, except left-aligned
+/// lines (code blocks) which are never wrapped.
+pub fn wrap(text: Text<'_>, width: u16) -> Text<'_> {
+    let width = usize::from(width);
+    if width == 0 {
+        return text;
+    }
+    let mut wrapped = Text::default().style(text.style);
+    wrapped.alignment = text.alignment;
+    for line in text.lines {
+        if line.alignment == Some(Alignment::Left) || line.width() <= width {
+            wrapped.lines.push(line);
+        } else {
+            wrapped.lines.extend(wrap_line(line, width));
+        }
+    }
+    wrapped
+}
+
+fn wrap_line(line: Line<'_>, width: usize) -> Vec<Line<'_>> {
+    let new_line = || Line {
+        spans: vec![],
+        style: line.style,
+        alignment: line.alignment,
+    };
+    let mut lines = vec![];
+    let mut current = new_line();
+    for span in &line.spans {
+        for word in span.content.split_inclusive(' ') {
+            let word_width = Span::raw(word.trim_end()).width();
+            if current.width() + word_width > width && current.width() > 0 {
+                lines.push(trim_end(std::mem::replace(&mut current, new_line())));
+            }
+            if current.width() == 0 && word.trim().is_empty() {
+                continue;
+            }
+            // Break words that don't fit on a line of their own
+            let mut rest = word;
+            while Span::raw(rest.trim_end()).width() > width.saturating_sub(current.width()) {
+                let room = width.saturating_sub(current.width());
+                let split = rest
+                    .char_indices()
+                    .scan(0, |w, (i, c)| {
+                        *w += Span::raw(c.to_string()).width();
+                        Some((i + c.len_utf8(), *w))
+                    })
+                    .take_while(|(_, w)| *w <= room)
+                    .last()
+                    .map_or(0, |(i, _)| i);
+                if split == 0 {
+                    if current.width() == 0 {
+                        break;
+                    }
+                    lines.push(trim_end(std::mem::replace(&mut current, new_line())));
+                    continue;
+                }
+                current
+                    .spans
+                    .push(Span::styled(rest[..split].to_string(), span.style));
+                lines.push(trim_end(std::mem::replace(&mut current, new_line())));
+                rest = &rest[split..];
+            }
+            current
+                .spans
+                .push(Span::styled(rest.to_string(), span.style));
+        }
+    }
+    if current.width() > 0 {
+        lines.push(trim_end(current));
+    }
+    lines
+}
+
+/// Drop the whitespace a line was broken at.
+fn trim_end(mut line: Line<'_>) -> Line<'_> {
+    while let Some(last) = line.spans.last_mut() {
+        let trimmed = last.content.trim_end();
+        if trimmed.is_empty() {
+            line.spans.pop();
+        } else {
+            last.content = trimmed.to_string().into();
+            break;
+        }
+    }
+    line
+}
+
+fn inline_code_style() -> Style {
+    Style::new().fg(Color::Cyan)
+}
+
+struct CodeBlock {
+    language: Option<String>,
+    text: String,
+}
+
+#[derive(Default)]
+struct Renderer {
+    lines: Vec<Line<'static>>,
+    spans: Vec<Span<'static>>,
+    /// Inline styles in effect, innermost last
+    styles: Vec<Style>,
+    /// Enclosing lists, with the next item number for ordered lists
+    lists: Vec<Option<u64>>,
+    /// The next line starts a list item and gets its bullet
+    item_start: bool,
+    quote_depth: usize,
+    /// The code block being read
+    code: Option<CodeBlock>,
+    /// Separate the next top-level block with a blank line
+    gap: bool,
+}
+
+impl Renderer {
+    fn event(&mut self, event: Event) {
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) => match &mut self.code {
+                Some(code) => code.text.push_str(&text),
+                None => self.push_text(&text),
+            },
+            Event::Code(text) | Event::InlineMath(text) | Event::DisplayMath(text) => {
+                self.spans
+                    .push(Span::styled(text.to_string(), inline_code_style()));
+            }
+            Event::Html(text) | Event::InlineHtml(text) => self.push_text(&text),
+            Event::SoftBreak | Event::HardBreak => self.flush_line(),
+            Event::Rule => {
+                self.block_start();
+                self.lines.push(Line::from("───".dark_gray()));
+                self.block_end();
+            }
+            Event::TaskListMarker(done) => {
+                self.spans
+                    .push(Span::raw(if done { "[x] " } else { "[ ] " }));
+            }
+            Event::FootnoteReference(name) => self.push_text(&format!("[^{name}]")),
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Paragraph => self.block_start(),
+            Tag::Heading { level, .. } => {
+                self.block_start();
+                let style = Style::new().bold();
+                self.styles
+                    .push(if level == pulldown_cmark::HeadingLevel::H1 {
+                        style.underlined()
+                    } else {
+                        style
+                    });
+            }
+            Tag::BlockQuote(_) => {
+                self.block_start();
+                self.quote_depth += 1;
+            }
+            Tag::CodeBlock(kind) => {
+                self.block_start();
+                let language = match kind {
+                    CodeBlockKind::Fenced(info) => info
+                        .split(|c: char| c.is_whitespace() || c == ',' || c == '{')
+                        .next()
+                        .filter(|l| !l.is_empty())
+                        .map(str::to_owned),
+                    CodeBlockKind::Indented => None,
+                };
+                self.code = Some(CodeBlock {
+                    language,
+                    text: String::new(),
+                });
+            }
+            Tag::List(start) => {
+                if self.lists.is_empty() {
+                    self.block_start();
+                } else {
+                    self.flush_line();
+                }
+                self.lists.push(start);
+            }
+            Tag::Item => {
+                self.flush_line();
+                self.item_start = true;
+            }
+            Tag::Emphasis => self.push_style(Style::new().italic()),
+            Tag::Strong => self.push_style(Style::new().bold()),
+            Tag::Strikethrough => self.push_style(Style::new().crossed_out()),
+            Tag::Link { .. } | Tag::Image { .. } => self.push_style(Style::new().underlined()),
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Paragraph => {
+                self.flush_line();
+                self.block_end();
+            }
+            TagEnd::Heading(_) => {
+                self.flush_line();
+                self.styles.pop();
+                self.block_end();
+            }
+            TagEnd::BlockQuote(_) => {
+                self.flush_line();
+                self.quote_depth -= 1;
+                self.block_end();
+            }
+            TagEnd::CodeBlock => {
+                let Some(code) = self.code.take() else {
+                    return;
+                };
+                let lines = code
+                    .language
+                    .as_deref()
+                    .and_then(|language| highlight_code(&code.text, language))
+                    .unwrap_or_else(|| {
+                        code.text
+                            .lines()
+                            .map(|l| vec![Span::raw(l.replace('\t', "    "))])
+                            .collect()
+                    });
+                for spans in lines {
+                    let mut line = vec![Span::raw(self.prefix())];
+                    line.extend(spans);
+                    self.lines.push(Line::from(line).left_aligned());
+                }
+                self.block_end();
+            }
+            TagEnd::List(_) => {
+                self.flush_line();
+                self.lists.pop();
+                self.block_end();
+            }
+            TagEnd::Item => {
+                self.flush_line();
+                self.item_start = false;
+                if let Some(Some(n)) = self.lists.last_mut() {
+                    *n += 1;
+                }
+            }
+            TagEnd::Emphasis
+            | TagEnd::Strong
+            | TagEnd::Strikethrough
+            | TagEnd::Link
+            | TagEnd::Image => {
+                self.styles.pop();
+            }
+            _ => {}
+        }
+    }
+
+    fn push_style(&mut self, style: Style) {
+        self.styles.push(style);
+    }
+
+    fn push_text(&mut self, text: &str) {
+        let style = self
+            .styles
+            .iter()
+            .fold(Style::new(), |acc, style| acc.patch(*style));
+        for (i, line) in text.split('\n').enumerate() {
+            if i > 0 {
+                self.flush_line();
+            }
+            if !line.is_empty() {
+                self.spans.push(Span::styled(line.to_string(), style));
+            }
+        }
+    }
+
+    fn block_start(&mut self) {
+        self.flush_line();
+        if self.gap {
+            self.lines.push(Line::default());
+            self.gap = false;
+        }
+    }
+
+    fn block_end(&mut self) {
+        if self.lists.is_empty() && self.quote_depth == 0 {
+            self.gap = true;
+        }
+    }
+
+    /// Quote bars and list indentation (or bullet) for the next line.
+    fn prefix(&mut self) -> String {
+        let mut prefix = "│ ".repeat(self.quote_depth);
+        if let Some(list) = self.lists.last() {
+            prefix.push_str(&"  ".repeat(self.lists.len() - 1));
+            if self.item_start {
+                self.item_start = false;
+                match list {
+                    Some(n) => prefix.push_str(&format!("{n}. ")),
+                    None => prefix.push_str("• "),
+                }
+            } else {
+                prefix.push_str("  ");
+            }
+        }
+        prefix
+    }
+
+    fn flush_line(&mut self) {
+        if self.spans.is_empty() {
+            return;
+        }
+        let prefix = self.prefix();
+        let mut spans = Vec::with_capacity(self.spans.len() + 1);
+        if !prefix.is_empty() {
+            spans.push(Span::raw(prefix).dark_gray());
+        }
+        spans.append(&mut self.spans);
+        self.lines.push(Line::from(spans));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plain(lines: &[Line]) -> Vec<String> {
+        lines.iter().map(|l| l.to_string()).collect()
+    }
+
+    #[test]
+    fn test_plain_lines_are_kept() {
+        let lines = render_markdown("V = IR\nVoltage equals current times resistance.");
+        assert_eq!(
+            plain(&lines),
+            vec!["V = IR", "Voltage equals current times resistance."]
+        );
+    }
+
+    #[test]
+    fn test_emphasis() {
+        let lines = render_markdown("A *b* **c** `d` ~~e~~");
+        assert_eq!(plain(&lines), vec!["A b c d e"]);
+        let spans = &lines[0].spans;
+        assert!(spans[1].style.add_modifier.contains(Modifier::ITALIC));
+        assert!(spans[3].style.add_modifier.contains(Modifier::BOLD));
+        assert_eq!(spans[5].style.fg, Some(Color::Cyan));
+        assert!(spans[7].style.add_modifier.contains(Modifier::CROSSED_OUT));
+    }
+
+    #[test]
+    fn test_lists_and_quotes() {
+        let lines = render_markdown("- one\n- two\n  1. nested\n  2. more\n\n> quoted\n> text");
+        assert_eq!(
+            plain(&lines),
+            vec![
+                "• one",
+                "• two",
+                "  1. nested",
+                "  2. more",
+                "",
+                "│ quoted",
+                "│ text"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_code_block_left_aligned() {
+        let lines = render_markdown("Before\n```rust\nfn main() {\n\tlet x = 1;\n}\n```\nAfter");
+        assert_eq!(
+            plain(&lines),
+            vec![
+                "Before",
+                "",
+                "fn main() {",
+                "    let x = 1;",
+                "}",
+                "",
+                "After"
+            ]
+        );
+        assert_eq!(lines[3].alignment, Some(Alignment::Left));
+        assert_eq!(lines[0].alignment, None);
+        // Highlighted by language
+        assert!(lines[2].spans.iter().any(|s| s.style.fg.is_some()));
+    }
+
+    #[test]
+    fn test_code_block_unknown_language() {
+        let lines = render_markdown("```klingon\nqapla'\n```\n~~~\nplain\n~~~");
+        assert_eq!(plain(&lines), vec!["qapla'", "", "plain"]);
+        assert!(lines
+            .iter()
+            .flat_map(|l| &l.spans)
+            .all(|s| s.style.fg.is_none()));
+    }
+
+    #[test]
+    fn test_wrap() {
+        let text = Text::from(vec![
+            Line::from(vec!["hello ".into(), "big".bold(), " world".into()]),
+            Line::from("    let very_long_code_line = 1;").left_aligned(),
+            Line::from("abcdefghij"),
+        ]);
+        let wrapped = wrap(text, 8);
+        assert_eq!(
+            plain(&wrapped.lines),
+            vec![
+                "hello",
+                "big",
+                "world",
+                "    let very_long_code_line = 1;",
+                "abcdefgh",
+                "ij"
+            ]
+        );
+        assert!(wrapped.lines[1].spans[0]
+            .style
+            .add_modifier
+            .contains(Modifier::BOLD));
+    }
+}
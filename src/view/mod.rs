@@ -5,6 +5,8 @@ use std::io::{stdout, Stdout};
 
 pub mod audit;
 pub mod formatting;
+mod highlight;
+pub mod markdown;
 pub mod revise;
 
 /// A type alias for the terminal type used in this application
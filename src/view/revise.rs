@@ -1,12 +1,16 @@
 use crate::algorithm::{update_meanq, Algorithm, Quality};
-use crate::view::formatting::{format_datetime_opt, format_tags};
+use crate::card::{render_cloze, CardKind, TYPE_TAG};
+use crate::similarity::{diff, levenshtein, normalise_answer, similarity, Diff};
+use crate::view::formatting::{format_breadcrumb, format_datetime_opt, format_tags};
+use crate::view::markdown::{self, render_markdown};
 use anyhow::Result;
+use rand::seq::SliceRandom;
 use rand::Rng;
 use ratatui::prelude::*;
 use std::io;
 use std::time::{Duration, Instant};
 
-use crate::db::{CardEntry, GlobalState};
+use crate::db::{CardEntry, GlobalState, ReviewLogEntry};
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
 use ratatui::{
     symbols::border,
@@ -19,7 +23,19 @@ struct UiState {
     help: bool,
     revealed: bool,
     started: Instant,
+    /// When the current card was shown, moved forward by the time spent on
+    /// the help screen.
+    shown: Instant,
+    help_opened: Instant,
     status_message: Option<String>,
+    /// Option picked on a multiple-choice card, and the grade it suggests.
+    choice: Option<usize>,
+    suggested: Option<Quality>,
+    /// Answer being typed, and the submitted answer once Enter is pressed.
+    input: String,
+    typed: Option<String>,
+    /// Hints revealed for the current card.
+    hints_shown: usize,
 }
 
 pub struct ReviseConfig {
@@ -27,6 +43,51 @@ pub struct ReviseConfig {
     pub max_duration: usize,
     pub reverse_probability: f64,
     pub tags: Vec<String>,
+    /// Type in the answer to every card, not just those tagged `#type`.
+    pub type_answers: bool,
+    /// Offer the first letters of the answer as a last hint.
+    pub letter_hints: bool,
+}
+
+/// A typed answer may be off by one edit per this many characters of the
+/// expected answer and still pass.
+const CHARS_PER_TYPO: usize = 4;
+
+/// The first letter of each word in `text`, with the rest blanked out,
+/// e.g. `P____ s___`.
+fn letter_hint(text: &str) -> String {
+    let mut word_start = true;
+    text.chars()
+        .map(|c| {
+            let blank = c.is_alphanumeric() && !word_start;
+            word_start = !c.is_alphanumeric();
+            if blank {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+/// Grades above this are lowered to it once a hint has been shown.
+const MAX_QUALITY_WITH_HINTS: Quality = Quality::CorrectWithHesitation;
+
+/// Grade suggested for a typed answer. Case and whitespace are ignored, and
+/// answers within a few typos still pass with a lower grade.
+fn suggest_quality(typed: &str, expected: &str) -> Quality {
+    let (typed, expected) = (normalise_answer(typed), normalise_answer(expected));
+    if typed.is_empty() {
+        return Quality::IncorrectAndForgotten;
+    }
+    let tolerance = expected.chars().count() / CHARS_PER_TYPO;
+    match levenshtein(&typed, &expected) {
+        0 => Quality::Perfect,
+        d if d <= tolerance => Quality::CorrectWithHesitation,
+        d if d <= 2 * tolerance => Quality::CorrectWithDifficulty,
+        _ if similarity(&typed, &expected) >= 0.5 => Quality::IncorrectButEasyToRecall,
+        _ => Quality::IncorrectAndForgotten,
+    }
 }
 
 pub struct App {
@@ -36,8 +97,13 @@ pub struct App {
     config: ReviseConfig,
     // Whether each card should be reversed for this session
     reverse_map: Vec<bool>,
+    // Shuffled option order of each multiple-choice card
+    option_order: Vec<Vec<usize>>,
+    // Saves a card as soon as it is graded
     #[allow(clippy::type_complexity)]
-    update_fn: Box<dyn Fn(Vec<CardEntry>, &GlobalState) -> Result<()>>,
+    update_fn: Box<dyn Fn(&CardEntry, &GlobalState, &ReviewLogEntry) -> Result<()>>,
+    // Grades that could not be saved
+    save_failures: usize,
     ui: UiState,
 }
 
@@ -48,26 +114,51 @@ impl App {
         cards: Vec<CardEntry>,
         global_state: GlobalState,
         config: ReviseConfig,
-        update_fn: Box<dyn Fn(Vec<CardEntry>, &GlobalState) -> Result<()>>,
+        update_fn: Box<dyn Fn(&CardEntry, &GlobalState, &ReviewLogEntry) -> Result<()>>,
     ) -> Self {
         let mut rng = rand::rng();
-        let reverse_map = (0..cards.len())
-            .map(|_| rng.random::<f64>() < config.reverse_probability)
+        // Cloze cards already hide part of the prompt, so never reverse them
+        let reverse_map = cards
+            .iter()
+            .map(|c| {
+                rng.random::<f64>() < config.reverse_probability
+                    && matches!(c.card.kind, CardKind::Basic)
+            })
+            .collect();
+        let option_order = cards
+            .iter()
+            .map(|c| match &c.card.kind {
+                CardKind::Choice { options, .. } => {
+                    let mut order: Vec<usize> = (0..options.len()).collect();
+                    order.shuffle(&mut rng);
+                    order
+                }
+                _ => vec![],
+            })
             .collect();
         Self {
             algorithm,
             cards,
             update_fn,
+            save_failures: 0,
             global_state,
             config,
             reverse_map,
+            option_order,
             ui: UiState {
                 current_card: 0,
                 exit: false,
                 help: false,
                 revealed: false,
                 started: Instant::now(),
+                shown: Instant::now(),
+                help_opened: Instant::now(),
                 status_message: None,
+                choice: None,
+                suggested: None,
+                input: String::new(),
+                typed: None,
+                hints_shown: 0,
             },
         }
     }
@@ -85,6 +176,10 @@ impl App {
         self.ui.current_card.min(self.cards.len())
     }
 
+    pub fn save_failures(&self) -> usize {
+        self.save_failures
+    }
+
     fn render_frame(&self, frame: &mut Frame) {
         frame.render_widget(self, frame.area());
     }
@@ -109,8 +204,14 @@ impl App {
     }
 
     fn update_state(&mut self, quality: Quality) {
+        let quality = self.capped(quality);
+        let hints_shown = std::mem::take(&mut self.ui.hints_shown);
         self.ui.revealed = false;
         self.ui.status_message = None;
+        self.ui.choice = None;
+        self.ui.suggested = None;
+        self.ui.input.clear();
+        self.ui.typed = None;
 
         if self.cards.is_empty() {
             return;
@@ -129,27 +230,75 @@ impl App {
 
         // Update the current card's state
         if let Some(card) = self.cards.get_mut(current_card) {
-            card.last_revised = Some(chrono::Utc::now());
+            let now = chrono::Utc::now();
+            let before = card.state.clone();
+            card.last_revised = Some(now);
             card.revise_count += 1;
-            self.algorithm
-                .update_state(&quality, &mut card.state, &mut self.global_state);
+            card.hints_used += hints_shown as u64;
+            self.algorithm.review(
+                &quality,
+                &mut card.state,
+                &mut self.global_state,
+                &card.card.directives,
+            );
 
             // Check if card should be marked as leech
             if !card.leech && card.state.failed_count >= self.config.leech_threshold as u64 {
                 card.leech = true;
                 self.ui.status_message = Some("Card marked as leech".to_string());
             }
+
+            let review = ReviewLogEntry {
+                card_id: card.card.id,
+                reviewed_at: now,
+                quality,
+                algorithm: self.algorithm.name().to_string(),
+                interval_before: before.interval,
+                interval_after: card.state.interval,
+                ease_before: before.ease_factor,
+                ease_after: card.state.ease_factor,
+                reversed: self.reverse_map.get(current_card).copied().unwrap_or(false)
+                    || matches!(card.card.kind, CardKind::Sided { reversed: true }),
+                duration_ms: self.ui.shown.elapsed().as_millis() as u64,
+                hints_used: hints_shown as u64,
+            };
+            if let Err(e) = (self.update_fn)(card, &self.global_state, &review) {
+                log::error!("Failed to save review: {e:#}");
+                self.save_failures += 1;
+                self.ui.status_message = Some(format!("Failed to save review: {e:#}"));
+            }
         }
 
         // Move to next card
         self.ui.current_card += 1;
+        self.ui.shown = Instant::now();
+    }
+
+    /// Time on the help screen doesn't count towards a review's duration.
+    fn toggle_help(&mut self) {
+        if self.ui.help {
+            self.ui.shown += self.ui.help_opened.elapsed();
+        } else {
+            self.ui.help_opened = Instant::now();
+        }
+        self.ui.help = !self.ui.help;
     }
 
     fn handle_key_event(&mut self, key_event: KeyEvent) {
+        if !self.ui.help && !self.ui.revealed && self.expected_answer().is_some() {
+            self.handle_input(key_event);
+            return;
+        }
+        // Number keys pick an option until a multiple-choice card is revealed
+        if let KeyCode::Char(c @ '1'..='9') = key_event.code {
+            if !self.ui.help && !self.ui.revealed && self.choose(c as usize - '1' as usize) {
+                return;
+            }
+        }
         match key_event.code {
             KeyCode::Char('q') | KeyCode::Char('Q') => {
                 if self.ui.help {
-                    self.ui.help = false;
+                    self.toggle_help();
                 } else {
                     self.exit();
                 }
@@ -158,7 +307,13 @@ impl App {
                 self.ui.revealed = true;
                 self.ui.status_message = None;
             }
-            KeyCode::Char('?') => self.ui.help = !self.ui.help,
+            KeyCode::Char('?') => self.toggle_help(),
+            KeyCode::Char('h') | KeyCode::Char('H') if !self.ui.help => self.show_hint(),
+            KeyCode::Enter if !self.ui.help => {
+                if let Some(quality) = self.ui.suggested {
+                    self.update_state(quality)
+                }
+            }
             KeyCode::Char('0') | KeyCode::Char('a') if !self.ui.help => {
                 self.try_grade(Quality::IncorrectAndForgotten)
             }
@@ -181,6 +336,115 @@ impl App {
         }
     }
 
+    /// The answer to type in for the current card, if it is typed in.
+    fn expected_answer(&self) -> Option<String> {
+        let current = self.ui.current_card;
+        let card = &self.cards.get(current)?.card;
+        if !self.config.type_answers && !card.tags.contains(TYPE_TAG) {
+            return None;
+        }
+        match card.kind {
+            CardKind::Choice { .. } => None,
+            CardKind::Cloze { .. } => Some(card.response.join(", ")),
+            _ if self.reverse_map[current] => Some(card.prompt.clone()),
+            _ => Some(card.response.join(" ")),
+        }
+    }
+
+    /// Edit the typed answer. Enter submits it, Esc reveals without one.
+    fn handle_input(&mut self, key_event: KeyEvent) {
+        match key_event.code {
+            KeyCode::Char(c) => self.ui.input.push(c),
+            KeyCode::Backspace => {
+                self.ui.input.pop();
+            }
+            KeyCode::Enter => {
+                let typed = std::mem::take(&mut self.ui.input);
+                self.ui.suggested = self
+                    .expected_answer()
+                    .map(|expected| suggest_quality(&typed, &expected));
+                self.ui.typed = Some(typed);
+                self.ui.revealed = true;
+            }
+            KeyCode::Esc => self.ui.revealed = true,
+            KeyCode::Tab => {
+                self.show_hint();
+                return;
+            }
+            _ => {}
+        }
+        self.ui.status_message = None;
+    }
+
+    /// Hints for the current card: its `hint:` lines, then the first letters
+    /// of the answer if letter hints are on.
+    fn hints(&self) -> Vec<String> {
+        let current = self.ui.current_card;
+        let Some(entry) = self.cards.get(current) else {
+            return vec![];
+        };
+        let card = &entry.card;
+        let reversed = self.reverse_map[current];
+        // Written hints are about the response, so not shown when reversed
+        let mut hints = if reversed { vec![] } else { card.hints.clone() };
+        if self.config.letter_hints {
+            match card.kind {
+                CardKind::Choice { .. } => {}
+                CardKind::Cloze { .. } => hints.push(letter_hint(&card.response.join(", "))),
+                _ if reversed => hints.push(letter_hint(&card.prompt)),
+                _ => hints.push(letter_hint(&card.response.join("\n"))),
+            }
+        }
+        hints
+    }
+
+    /// Reveal the next hint of the current card.
+    fn show_hint(&mut self) {
+        if self.ui.revealed {
+            return;
+        }
+        if self.ui.hints_shown < self.hints().len() {
+            self.ui.hints_shown += 1;
+            self.ui.status_message = None;
+        } else {
+            self.ui.status_message = Some("No more hints".to_string());
+        }
+    }
+
+    /// Lower `quality` below perfect if hints were used.
+    fn capped(&self, quality: Quality) -> Quality {
+        if self.ui.hints_shown > 0 && quality as u8 > MAX_QUALITY_WITH_HINTS as u8 {
+            MAX_QUALITY_WITH_HINTS
+        } else {
+            quality
+        }
+    }
+
+    /// Pick the `n`th displayed option of the current multiple-choice card,
+    /// revealing the answer and suggesting a grade. Returns false if the
+    /// card is not a multiple-choice card.
+    fn choose(&mut self, n: usize) -> bool {
+        let current = self.ui.current_card;
+        let Some(CardKind::Choice { answer, .. }) = self.cards.get(current).map(|c| &c.card.kind)
+        else {
+            return false;
+        };
+        match self.option_order[current].get(n) {
+            Some(&choice) => {
+                self.ui.choice = Some(choice);
+                self.ui.suggested = Some(if choice == *answer {
+                    Quality::Perfect
+                } else {
+                    Quality::IncorrectAndForgotten
+                });
+                self.ui.revealed = true;
+                self.ui.status_message = None;
+            }
+            None => self.ui.status_message = Some(format!("No option {}", n + 1)),
+        }
+        true
+    }
+
     fn try_grade(&mut self, quality: Quality) {
         if self.ui.revealed {
             self.update_state(quality);
@@ -194,9 +458,6 @@ impl App {
     }
 
     fn exit(&mut self) {
-        if let Err(e) = (self.update_fn)(std::mem::take(&mut self.cards), &self.global_state) {
-            log::error!("Failed to update cards during exit: {e}");
-        }
         self.ui.exit = true;
     }
 
@@ -264,6 +525,25 @@ impl App {
                 Quality::Perfect
             )
             .green()]),
+            Line::from(vec![]),
+            Line::from(vec!["Multiple choice".into()]),
+            Line::from(vec![]),
+            Line::from(vec!["1-9: pick an option".into()]),
+            Line::from(vec!["Enter: accept the suggested grade".into()]),
+            Line::from(vec![]),
+            Line::from(vec!["Typed answers".into()]),
+            Line::from(vec![]),
+            Line::from(vec!["Enter: submit the answer".into()]),
+            Line::from(vec!["Esc: reveal without answering".into()]),
+            Line::from(vec!["Tab: show a hint".into()]),
+            Line::from(vec![]),
+            Line::from(vec!["Hints".into()]),
+            Line::from(vec![]),
+            Line::from(vec![format!(
+                "h: show the next hint (caps the grade at {})",
+                MAX_QUALITY_WITH_HINTS as u8
+            )
+            .into()]),
         ]);
         (block, counter_text)
     }
@@ -274,10 +554,18 @@ impl App {
             .get(self.ui.current_card)
             .copied()
             .unwrap_or(false);
+        let reverse_side = self
+            .cards
+            .get(self.ui.current_card)
+            .is_some_and(|c| matches!(c.card.kind, CardKind::Sided { reversed: true }));
         let title = Line::from(
             format!(
                 " {} Revise Cards {}/{} [{} | algo:{} | rev:{:.2}] ",
-                if reversed { "[Reversed]" } else { "" },
+                if reversed || reverse_side {
+                    "[Reversed]"
+                } else {
+                    ""
+                },
                 std::cmp::min(self.cards.len(), 1 + self.ui.current_card),
                 self.cards.len(),
                 if self.config.tags.is_empty() {
@@ -332,32 +620,107 @@ impl App {
                 lines.push(Line::from(vec![format_tags(&card.card.tags).into()]));
                 lines.push(Line::from(vec![]));
             }
-            if !reversed {
-                lines.push(Line::from(vec!["Prompt".bold()]));
-                lines.push(Line::from(vec![card.card.prompt.clone().into()]));
+            if !card.card.breadcrumb.is_empty() {
+                lines.push(Line::from(vec![
+                    format_breadcrumb(&card.card.breadcrumb).dim()
+                ]));
+            }
+            if let CardKind::Cloze { index, .. } = card.card.kind {
+                lines.push(Line::from(vec!["Cloze".bold()]));
+                lines.extend(render_markdown(&render_cloze(
+                    &card.card.prompt,
+                    index,
+                    self.ui.revealed,
+                )));
                 lines.push(Line::from(vec![]));
                 lines.push(Line::from(vec!["Response".bold()]));
                 if self.ui.revealed {
-                    for l in card.card.response.iter() {
-                        lines.push(Line::from(vec![l.into()]));
+                    lines.push(Line::from(vec![card.card.response.join(", ").into()]));
+                } else {
+                    lines.push(Line::from(vec!["<hidden>".into()]));
+                }
+            } else if let CardKind::Choice { options, answer } = &card.card.kind {
+                lines.push(Line::from(vec!["Prompt".bold()]));
+                lines.extend(render_markdown(&card.card.prompt));
+                lines.push(Line::from(vec![]));
+                lines.push(Line::from(vec!["Options".bold()]));
+                for (n, &i) in self.option_order[self.ui.current_card].iter().enumerate() {
+                    let number = format!("{}. ", n + 1);
+                    let indent = " ".repeat(number.len());
+                    let mut option = render_markdown(&options[i]);
+                    if option.is_empty() {
+                        option.push(Line::default());
                     }
+                    for (j, line) in option.iter_mut().enumerate() {
+                        let prefix = if j == 0 {
+                            number.clone().bold()
+                        } else {
+                            indent.clone().into()
+                        };
+                        line.spans.insert(0, prefix);
+                    }
+                    let style = if !self.ui.revealed {
+                        Style::new()
+                    } else if i == *answer {
+                        Style::new().green().bold()
+                    } else if self.ui.choice == Some(i) {
+                        Style::new().red().crossed_out()
+                    } else {
+                        Style::new().dim()
+                    };
+                    lines.extend(option.into_iter().map(|line| line.patch_style(style)));
+                }
+            } else if !reversed {
+                lines.push(Line::from(vec!["Prompt".bold()]));
+                lines.extend(render_markdown(&card.card.prompt));
+                lines.push(Line::from(vec![]));
+                lines.push(Line::from(vec!["Response".bold()]));
+                if self.ui.revealed {
+                    lines.extend(render_markdown(&card.card.response.join("\n")));
                 } else {
                     lines.push(Line::from(vec!["<hidden>".into()]));
                 }
             } else {
                 // Reversed: show response as the prompt; hide the original prompt until reveal
                 lines.push(Line::from(vec!["Prompt".bold()]));
-                for l in card.card.response.iter() {
-                    lines.push(Line::from(vec![l.into()]));
-                }
+                lines.extend(render_markdown(&card.card.response.join("\n")));
                 lines.push(Line::from(vec![]));
                 lines.push(Line::from(vec!["Response".bold()]));
                 if self.ui.revealed {
-                    lines.push(Line::from(vec![card.card.prompt.clone().into()]));
+                    lines.extend(render_markdown(&card.card.prompt));
                 } else {
                     lines.push(Line::from(vec!["<hidden>".into()]));
                 }
             }
+            let hints = self.hints();
+            if (!hints.is_empty() && !self.ui.revealed) || self.ui.hints_shown > 0 {
+                lines.push(Line::from(vec![]));
+                lines.push(Line::from(vec![format!(
+                    "Hints {}/{}",
+                    self.ui.hints_shown,
+                    hints.len()
+                )
+                .bold()]));
+                for hint in &hints[..self.ui.hints_shown] {
+                    lines.push(Line::from(vec![hint.clone().italic()]));
+                }
+            }
+            if let Some(expected) = self.expected_answer() {
+                lines.push(Line::from(vec![]));
+                lines.push(Line::from(vec!["Your Answer".bold()]));
+                lines.push(match &self.ui.typed {
+                    Some(typed) => answer_diff(typed, &expected),
+                    None if self.ui.revealed => Line::from(vec!["<skipped>".dim()]),
+                    None => Line::from(vec![format!("> {}▏", self.ui.input).into()]),
+                });
+            }
+            if let Some(quality) = self.ui.suggested.map(|q| self.capped(q)) {
+                lines.push(Line::from(vec![]));
+                lines.push(Line::from(vec![
+                    format!("Suggested grade: {} ({quality:?}) ", quality as u8).bold(),
+                    "<Enter> to accept, <0-5> to override".into(),
+                ]));
+            }
             lines.push(Line::from(vec![]));
             lines.push(Line::from(vec!["Last Revised".bold()]));
             lines.push(Line::from(vec![format_datetime_opt(
@@ -376,6 +739,23 @@ impl App {
     }
 }
 
+/// The typed answer marked up against the expected one: extra characters
+/// struck through in red, missing ones underlined in green.
+fn answer_diff(typed: &str, expected: &str) -> Line<'static> {
+    if typed.trim().is_empty() {
+        return Line::from(vec!["<empty>".dim()]);
+    }
+    let spans: Vec<Span> = diff(typed.trim(), expected.trim())
+        .into_iter()
+        .map(|d| match d {
+            Diff::Same(s) => s.into(),
+            Diff::Removed(s) => s.red().crossed_out(),
+            Diff::Added(s) => s.green().underlined(),
+        })
+        .collect();
+    Line::from(spans)
+}
+
 impl Widget for &App {
     fn render(self, area: Rect, buf: &mut Buffer) {
         let (block, counter_text) = if self.ui.help {
@@ -383,8 +763,8 @@ impl Widget for &App {
         } else {
             self.card_revise()
         };
-        Paragraph::new(counter_text)
-            .wrap(Wrap { trim: true })
+        let width = block.inner(area).width;
+        Paragraph::new(markdown::wrap(counter_text, width))
             .centered()
             .block(block)
             .render(area, buf);
@@ -396,12 +776,13 @@ mod tests {
     use super::*;
     use crate::algorithm::new_algorithm;
     use crate::algorithm::Algo;
-    use crate::card::Card;
+    use crate::card::{Card, CardKind};
+    use std::cell::RefCell;
     use std::collections::HashSet;
     use std::path::PathBuf;
+    use std::rc::Rc;
 
-    fn create_test_app() -> App {
-        let algorithm = new_algorithm(Algo::SM2);
+    fn test_entries() -> Vec<CardEntry> {
         let card = Card {
             id: blake3::hash(b"test"),
             file: PathBuf::from("test.md"),
@@ -409,34 +790,57 @@ mod tests {
             prompt: "test prompt".to_string(),
             response: vec!["test response".to_string()],
             tags: HashSet::new(),
+            kind: CardKind::Basic,
+            breadcrumb: vec![],
+            hints: vec![],
+            directives: Default::default(),
         };
-        let cards = vec![CardEntry {
+        vec![CardEntry {
             added: chrono::Utc::now(),
             card,
             last_revised: None,
             revise_count: 0,
+            hints_used: 0,
             state: Default::default(),
             leech: false,
             orphan: false,
-        }];
-        let global_state = GlobalState::default();
-        fn update_fn(_cards: Vec<CardEntry>, _state: &GlobalState) -> Result<()> {
+        }]
+    }
+
+    fn test_config() -> ReviseConfig {
+        ReviseConfig {
+            leech_threshold: 3,
+            max_duration: 3600,
+            reverse_probability: 0.0,
+            tags: vec![],
+            type_answers: false,
+            letter_hints: false,
+        }
+    }
+
+    /// Build an app with a no-op `update_fn`; `reverse_map` is computed in
+    /// `App::new`, so config that affects it must be set here.
+    fn create_app(cards: Vec<CardEntry>, config: ReviseConfig) -> App {
+        fn update_fn(
+            _card: &CardEntry,
+            _state: &GlobalState,
+            _review: &ReviewLogEntry,
+        ) -> Result<()> {
             Ok(())
         }
         App::new(
-            algorithm,
+            new_algorithm(Algo::SM2),
             cards,
-            global_state,
-            ReviseConfig {
-                leech_threshold: 3,
-                max_duration: 3600,
-                reverse_probability: 0.0,
-                tags: vec![],
-            },
+            GlobalState::default(),
+            config,
             Box::new(update_fn),
         )
     }
 
+    fn create_test_app() -> App {
+        create_app(test_entries(), test_config())
+    }
+
     /// Simulate pressing space to reveal, then a quality key
     fn press_reveal_and_grade(app: &mut App, key: char) {
         app.handle_key_event(KeyEvent::new(KeyCode::Char(' '), event::KeyModifiers::NONE));
@@ -655,77 +1059,254 @@ mod tests {
 
     #[test]
     fn test_reverse_probability() {
-        // reverse_map is computed at construction time, so we must set
-        // reverse_probability before creating the App
-        let algorithm = new_algorithm(Algo::SM2);
-        let card = Card {
-            id: blake3::hash(b"test"),
-            file: PathBuf::from("test.md"),
-            line: 0,
-            prompt: "test prompt".to_string(),
-            response: vec!["test response".to_string()],
-            tags: HashSet::new(),
+        let reversed = ReviseConfig {
+            reverse_probability: 1.0,
+            ..test_config()
         };
-        let cards = vec![CardEntry {
-            added: chrono::Utc::now(),
-            card,
-            last_revised: None,
-            revise_count: 0,
-            state: Default::default(),
-            leech: false,
-            orphan: false,
-        }];
-        fn update_fn(_cards: Vec<CardEntry>, _state: &GlobalState) -> Result<()> {
-            Ok(())
-        }
-        let app = App::new(
-            algorithm,
-            cards,
-            GlobalState::default(),
-            ReviseConfig {
-                leech_threshold: 3,
-                max_duration: 3600,
-                reverse_probability: 1.0, // Always reverse
-                tags: vec![],
-            },
-            Box::new(update_fn),
-        );
+        assert!(create_app(test_entries(), reversed).reverse_map[0]);
+        assert!(!create_test_app().reverse_map[0]);
+    }
 
-        // With probability 1.0, all cards should be reversed
-        assert!(app.reverse_map[0]);
+    #[test]
+    fn test_cloze_cards_never_reversed() {
+        let mut cards = test_entries();
+        cards[0].card.kind = CardKind::Cloze {
+            group: blake3::hash(b"group"),
+            index: 1,
+        };
+        let config = ReviseConfig {
+            reverse_probability: 1.0,
+            ..test_config()
+        };
+        assert!(!create_app(cards, config).reverse_map[0]);
+    }
 
-        // Verify a 0.0 probability produces no reversals
-        let algorithm = new_algorithm(Algo::SM2);
-        let card = Card {
-            id: blake3::hash(b"test"),
-            file: PathBuf::from("test.md"),
-            line: 0,
-            prompt: "test prompt".to_string(),
-            response: vec!["test response".to_string()],
-            tags: HashSet::new(),
+    fn create_choice_app() -> App {
+        let mut cards = test_entries();
+        cards[0].card.kind = CardKind::Choice {
+            options: vec!["Rome".to_string(), "Paris".to_string(), "Oslo".to_string()],
+            answer: 1,
         };
-        let cards = vec![CardEntry {
-            added: chrono::Utc::now(),
-            card,
-            last_revised: None,
-            revise_count: 0,
-            state: Default::default(),
-            leech: false,
-            orphan: false,
-        }];
-        let app = App::new(
-            algorithm,
-            cards,
-            GlobalState::default(),
-            ReviseConfig {
-                leech_threshold: 3,
-                max_duration: 3600,
-                reverse_probability: 0.0, // Never reverse
-                tags: vec![],
-            },
-            Box::new(update_fn),
-        );
+        let config = ReviseConfig {
+            reverse_probability: 1.0,
+            ..test_config()
+        };
+        create_app(cards, config)
+    }
+
+    fn key(c: KeyCode) -> KeyEvent {
+        KeyEvent::new(c, event::KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn test_choice_options_shuffled_and_shown() {
+        let app = create_choice_app();
         assert!(!app.reverse_map[0]);
+        let mut order = app.option_order[0].clone();
+        order.sort();
+        assert_eq!(order, vec![0, 1, 2]);
+
+        let (_, text) = app.card_revise();
+        let lines: Vec<String> = text.lines.iter().map(|l| l.to_string()).collect();
+        for (n, &i) in app.option_order[0].iter().enumerate() {
+            let option = ["Rome", "Paris", "Oslo"][i];
+            assert!(lines.contains(&format!("{}. {option}", n + 1)));
+        }
+    }
+
+    #[test]
+    fn test_choice_multi_line_options_shown_in_full() {
+        let mut app = create_choice_app();
+        app.cards[0].card.kind = CardKind::Choice {
+            options: vec![
+                "Two lines\nof text".to_string(),
+                "```\nfn main() {}\n```".to_string(),
+            ],
+            answer: 0,
+        };
+        app.option_order[0] = vec![0, 1];
+        let (_, text) = app.card_revise();
+        let lines: Vec<String> = text.lines.iter().map(|l| l.to_string()).collect();
+        let at = lines.iter().position(|l| l == "1. Two lines").unwrap();
+        assert_eq!(lines[at + 1], "   of text");
+        assert!(lines[at + 2].starts_with("2. "));
+        assert!(lines.iter().any(|l| l.contains("fn main() {}")));
+    }
+
+    #[test]
+    fn test_choice_suggests_grade() {
+        let mut app = create_choice_app();
+        let correct = app.option_order[0].iter().position(|&i| i == 1).unwrap();
+        let wrong = (correct + 1) % 3;
+
+        // Out of range options are rejected
+        app.handle_key_event(key(KeyCode::Char('7')));
+        assert!(!app.ui.revealed);
+        assert!(app.ui.status_message.is_some());
+
+        let digit = |n: usize| KeyCode::Char(char::from(b'1' + n as u8));
+        app.handle_key_event(key(digit(wrong)));
+        assert!(app.ui.revealed);
+        assert_eq!(app.ui.suggested, Some(Quality::IncorrectAndForgotten));
+
+        // Accepting the suggestion grades the card
+        app.handle_key_event(key(KeyCode::Enter));
+        assert_eq!(app.ui.current_card, 1);
+        assert_eq!(app.cards[0].state.failed_count, 1);
+        assert!(app.ui.suggested.is_none());
+
+        // Number keys grade once the card is revealed, overriding the suggestion
+        let mut app = create_choice_app();
+        let correct = app.option_order[0].iter().position(|&i| i == 1).unwrap();
+        app.handle_key_event(key(digit(correct)));
+        assert_eq!(app.ui.suggested, Some(Quality::Perfect));
+        app.handle_key_event(key(KeyCode::Char('3')));
+        assert_eq!(app.ui.current_card, 1);
+        assert_eq!(app.cards[0].state.failed_count, 0);
+        assert_eq!(app.cards[0].revise_count, 1);
+    }
+
+    #[test]
+    fn test_suggest_quality() {
+        assert_eq!(suggest_quality("Paris", "paris"), Quality::Perfect);
+        assert_eq!(
+            suggest_quality(" ice  cream", "Ice Cream"),
+            Quality::Perfect
+        );
+        assert_eq!(
+            suggest_quality("recieve", "receive"),
+            Quality::CorrectWithDifficulty
+        );
+        assert_eq!(
+            suggest_quality("mitochondrian", "mitochondria"),
+            Quality::CorrectWithHesitation
+        );
+        assert_eq!(suggest_quality("b", "a"), Quality::IncorrectAndForgotten);
+        assert_eq!(suggest_quality("", "a"), Quality::IncorrectAndForgotten);
+        assert_eq!(
+            suggest_quality("London", "Paris"),
+            Quality::IncorrectAndForgotten
+        );
+        assert_eq!(
+            suggest_quality("Berliner", "Bern"),
+            Quality::IncorrectButEasyToRecall
+        );
+    }
+
+    #[test]
+    fn test_typed_answer() {
+        let mut app = create_test_app();
+        app.cards[0].card.tags.insert(TYPE_TAG.to_string());
+        assert_eq!(app.expected_answer().as_deref(), Some("test response"));
+
+        // Keys are typed into the answer, including grade and quit keys
+        for c in "Test  respons q".chars() {
+            app.handle_key_event(key(KeyCode::Char(c)));
+        }
+        app.handle_key_event(key(KeyCode::Backspace));
+        app.handle_key_event(key(KeyCode::Backspace));
+        assert!(!app.ui.exit);
+        assert!(!app.ui.revealed);
+        assert_eq!(app.ui.input, "Test  respons");
+
+        let (_, text) = app.card_revise();
+        assert!(text
+            .lines
+            .iter()
+            .any(|l| l.to_string() == "> Test  respons▏"));
+
+        app.handle_key_event(key(KeyCode::Enter));
+        assert!(app.ui.revealed);
+        assert_eq!(app.ui.suggested, Some(Quality::CorrectWithHesitation));
+        let (_, text) = app.card_revise();
+        let answer = text
+            .lines
+            .iter()
+            .find(|l| l.to_string() == "Test  response")
+            .expect("typed answer");
+        assert!(answer
+            .spans
+            .iter()
+            .any(|s| s.content == "e" && s.style.add_modifier.contains(Modifier::UNDERLINED)));
+
+        // Enter confirms the suggested grade
+        app.handle_key_event(key(KeyCode::Enter));
+        assert_eq!(app.ui.current_card, 1);
+        assert_eq!(app.cards[0].revise_count, 1);
+        assert!(app.ui.typed.is_none());
+    }
+
+    #[test]
+    fn test_type_answers_mode() {
+        let mut app = create_test_app();
+        assert!(app.expected_answer().is_none());
+        app.config.type_answers = true;
+        assert!(app.expected_answer().is_some());
+        app.reverse_map[0] = true;
+        assert_eq!(app.expected_answer().as_deref(), Some("test prompt"));
+
+        // Esc reveals without an answer or a suggestion
+        app.handle_key_event(key(KeyCode::Esc));
+        assert!(app.ui.revealed);
+        assert!(app.ui.suggested.is_none());
+        app.handle_key_event(key(KeyCode::Char('5')));
+        assert_eq!(app.ui.current_card, 1);
+    }
+
+    #[test]
+    fn test_letter_hint() {
+        assert_eq!(letter_hint("Paris"), "P____");
+        assert_eq!(letter_hint("on the Seine, 1789!"), "o_ t__ S____, 1___!");
+        assert_eq!(letter_hint(""), "");
+    }
+
+    #[test]
+    fn test_hints() {
+        let logged = Rc::new(RefCell::new(vec![]));
+        let mut app = create_test_app();
+        let sink = logged.clone();
+        app.update_fn = Box::new(move |_, _, review| {
+            sink.borrow_mut().push(review.hints_used);
+            Ok(())
+        });
+        app.cards[0].card.hints = vec!["first".to_string(), "second".to_string()];
+        app.config.letter_hints = true;
+        assert_eq!(app.hints(), vec!["first", "second", "t___ r_______"]);
+
+        let (_, text) = app.card_revise();
+        assert!(text.lines.iter().any(|l| l.to_string() == "Hints 0/3"));
+
+        app.handle_key_event(key(KeyCode::Char('h')));
+        app.handle_key_event(key(KeyCode::Char('h')));
+        assert_eq!(app.ui.hints_shown, 2);
+        let (_, text) = app.card_revise();
+        let lines: Vec<String> = text.lines.iter().map(|l| l.to_string()).collect();
+        assert!(lines.contains(&"Hints 2/3".to_string()));
+        assert!(lines.contains(&"second".to_string()));
+        assert!(!lines.contains(&"t___ r_______".to_string()));
+
+        app.handle_key_event(key(KeyCode::Char('h')));
+        app.handle_key_event(key(KeyCode::Char('h')));
+        assert_eq!(app.ui.hints_shown, 3);
+        assert_eq!(app.ui.status_message.as_deref(), Some("No more hints"));
+
+        // Grading after a hint is capped below perfect
+        app.handle_key_event(key(KeyCode::Char(' ')));
+        app.handle_key_event(key(KeyCode::Char('5')));
+        assert_eq!(app.cards[0].hints_used, 3);
+        assert_eq!(*logged.borrow(), vec![3]);
+        assert_eq!(app.global_state.mean_q, Some(4.0));
+        assert_eq!(app.ui.hints_shown, 0);
+    }
+
+    #[test]
+    fn test_reversed_cards_skip_written_hints() {
+        let mut app = create_test_app();
+        app.cards[0].card.hints = vec!["about the response".to_string()];
+        app.reverse_map[0] = true;
+        assert!(app.hints().is_empty());
+        app.config.letter_hints = true;
+        assert_eq!(app.hints(), vec!["t___ p_____"]);
     }
 
     #[test]
@@ -835,6 +1416,49 @@ mod tests {
         assert!(app.ui.revealed);
     }
 
+    #[test]
+    fn test_markdown_response_rendering() {
+        let mut app = create_test_app();
+        app.cards[0].card.prompt = "What does **this** print?".to_string();
+        app.cards[0].card.response = vec![
+            "```rust".to_string(),
+            "    println!(\"{}\", 1);".to_string(),
+            "```".to_string(),
+        ];
+        app.handle_key_event(KeyEvent::new(KeyCode::Char(' '), event::KeyModifiers::NONE));
+
+        let (_, text) = app.card_revise();
+        let prompt = text
+            .lines
+            .iter()
+            .find(|l| l.to_string() == "What does this print?")
+            .expect("rendered prompt");
+        assert!(prompt.spans[1].style.add_modifier.contains(Modifier::BOLD));
+        let code = text
+            .lines
+            .iter()
+            .find(|l| l.to_string().contains("println!"))
+            .expect("rendered code");
+        assert_eq!(code.to_string(), "    println!(\"{}\", 1);");
+        assert_eq!(code.alignment, Some(Alignment::Left));
+        assert!(!text.lines.iter().any(|l| l.to_string().contains("```")));
+    }
+
+    #[test]
+    fn test_breadcrumb_shown_above_prompt() {
+        let mut app = create_test_app();
+        app.cards[0].card.breadcrumb = vec!["Rust".to_string(), "Iterators".to_string()];
+
+        let (_, text) = app.card_revise();
+        let lines: Vec<String> = text.lines.iter().map(|l| l.to_string()).collect();
+        let crumb = lines
+            .iter()
+            .position(|l| l == "Rust > Iterators")
+            .expect("breadcrumb");
+        let prompt = lines.iter().position(|l| l == "Prompt").expect("prompt");
+        assert!(crumb < prompt);
+    }
+
     #[test]
     fn test_consecutive_failures() {
         let mut app = create_test_app();
@@ -874,6 +1498,64 @@ mod tests {
         assert!(app.global_state.last_revise_session.is_some());
     }
 
+    #[test]
+    fn test_update_state_saves_review() {
+        let logged = Rc::new(RefCell::new(vec![]));
+        let mut app = create_test_app();
+        let sink = logged.clone();
+        app.update_fn = Box::new(move |card, _, review| {
+            assert_eq!(card.revise_count, 1);
+            sink.borrow_mut().push(review.clone());
+            Ok(())
+        });
+        app.reverse_map[0] = true;
+        app.ui.shown = Instant::now() - Duration::from_secs(3);
+        app.update_state(Quality::Perfect);
+
+        let logged = logged.borrow();
+        assert_eq!(logged.len(), 1);
+        let entry = &logged[0];
+        assert_eq!(entry.card_id, app.cards[0].card.id);
+        assert_eq!(entry.quality, Quality::Perfect);
+        assert_eq!(entry.algorithm, app.algorithm.name());
+        assert_eq!(entry.interval_before, 0);
+        assert_eq!(entry.interval_after, app.cards[0].state.interval);
+        assert_eq!(entry.ease_after, app.cards[0].state.ease_factor);
+        assert!(entry.reversed);
+        assert!(entry.duration_ms >= 3000);
+    }
+
+    #[test]
+    fn test_help_time_not_in_review_duration() {
+        let logged = Rc::new(RefCell::new(vec![]));
+        let mut app = create_test_app();
+        let sink = logged.clone();
+        app.update_fn = Box::new(move |_, _, review| {
+            sink.borrow_mut().push(review.duration_ms);
+            Ok(())
+        });
+        app.ui.shown = Instant::now() - Duration::from_secs(13);
+        app.handle_key_event(key(KeyCode::Char('?')));
+        app.ui.help_opened = Instant::now() - Duration::from_secs(10);
+        app.handle_key_event(key(KeyCode::Char('q')));
+        app.update_state(Quality::Perfect);
+        let duration_ms = logged.borrow()[0];
+        assert!((3000..10_000).contains(&duration_ms), "{duration_ms}");
+    }
+
+    #[test]
+    fn test_save_failure_shown_in_status() {
+        let mut app = create_test_app();
+        app.update_fn = Box::new(|_, _, _| anyhow::bail!("disk full"));
+        app.update_state(Quality::Perfect);
+        assert_eq!(app.ui.current_card, 1);
+        assert_eq!(app.save_failures(), 1);
+        assert_eq!(
+            app.ui.status_message.as_deref(),
+            Some("Failed to save review: disk full")
+        );
+    }
+
     #[test]
     fn test_keyboard_shortcuts() {
         // Test various keyboard shortcuts
@@ -0,0 +1,40 @@
+-- A carddown database as created by schema version 3.
+CREATE TABLE cards (
+    id BLOB NOT NULL PRIMARY KEY,
+    file TEXT NOT NULL,
+    line INTEGER NOT NULL,
+    prompt TEXT NOT NULL,
+    response TEXT NOT NULL,
+    tags TEXT NOT NULL,
+    added TEXT NOT NULL,
+    last_revised TEXT,
+    revise_count INTEGER NOT NULL DEFAULT 0,
+    leech INTEGER NOT NULL DEFAULT 0,
+    orphan INTEGER NOT NULL DEFAULT 0,
+    ease_factor REAL NOT NULL DEFAULT 2.5,
+    interval INTEGER NOT NULL DEFAULT 0,
+    repetitions INTEGER NOT NULL DEFAULT 0,
+    failed_count INTEGER NOT NULL DEFAULT 0,
+    kind TEXT NOT NULL DEFAULT '{"type":"basic"}',
+    breadcrumb TEXT NOT NULL DEFAULT '[]'
+);
+CREATE INDEX idx_cards_status ON cards (orphan, leech);
+CREATE TABLE global_state (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    optimal_factor_matrix TEXT NOT NULL DEFAULT '{}',
+    last_revise_session TEXT,
+    mean_q REAL,
+    total_cards_revised INTEGER NOT NULL DEFAULT 0
+);
+CREATE TABLE scan_index (
+    file_path TEXT PRIMARY KEY,
+    mtime INTEGER NOT NULL
+);
+INSERT INTO cards (id, file, line, prompt, response, tags, added, last_revised, revise_count, leech, orphan, ease_factor, interval, repetitions, failed_count, kind, breadcrumb)
+VALUES
+    (X'1111111111111111111111111111111111111111111111111111111111111111', 'notes/geo.md', 3, 'Capital of France?', '["Paris"]', '["geo"]', '2024-01-01T00:00:00+00:00', '2024-03-01T12:00:00+00:00', 7, 0, 0, 2.36, 21, 6, 1, '{"type":"basic"}', '["Geography"]'),
+    (X'2222222222222222222222222222222222222222222222222222222222222222', 'notes/old.md', 1, 'Gone card', '["answer"]', '[]', '2024-01-01T00:00:00+00:00', NULL, 0, 1, 1, 2.5, 0, 0, 4, '{"type":"basic"}', '[]');
+INSERT INTO global_state (id, optimal_factor_matrix, last_revise_session, mean_q, total_cards_revised)
+VALUES (1, '{}', '2024-03-01T12:00:00+00:00', 3.5, 12);
+INSERT INTO scan_index (file_path, mtime) VALUES ('notes/geo.md', 1709294400);
+PRAGMA user_version = 3;
@@ -0,0 +1,83 @@
+-- A carddown database as created by schema version 8.
+CREATE TABLE cards (
+    id BLOB NOT NULL PRIMARY KEY,
+    file TEXT NOT NULL,
+    line INTEGER NOT NULL,
+    prompt TEXT NOT NULL,
+    response TEXT NOT NULL,
+    tags TEXT NOT NULL,
+    added TEXT NOT NULL,
+    last_revised TEXT,
+    revise_count INTEGER NOT NULL DEFAULT 0,
+    leech INTEGER NOT NULL DEFAULT 0,
+    orphan INTEGER NOT NULL DEFAULT 0,
+    ease_factor REAL NOT NULL DEFAULT 2.5,
+    interval INTEGER NOT NULL DEFAULT 0,
+    repetitions INTEGER NOT NULL DEFAULT 0,
+    failed_count INTEGER NOT NULL DEFAULT 0,
+    kind TEXT NOT NULL DEFAULT '{"type":"basic"}',
+    breadcrumb TEXT NOT NULL DEFAULT '[]',
+    hints TEXT NOT NULL DEFAULT '[]',
+    hints_used INTEGER NOT NULL DEFAULT 0,
+    directives TEXT NOT NULL DEFAULT '{}',
+    due_at INTEGER NOT NULL DEFAULT 0,
+    priority INTEGER NOT NULL DEFAULT 1,
+    suspended INTEGER NOT NULL DEFAULT 0
+);
+CREATE INDEX idx_cards_status ON cards (orphan, leech);
+CREATE TABLE global_state (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    optimal_factor_matrix TEXT NOT NULL DEFAULT '{}',
+    last_revise_session TEXT,
+    mean_q REAL,
+    total_cards_revised INTEGER NOT NULL DEFAULT 0
+);
+CREATE TABLE scan_index (
+    file_path TEXT PRIMARY KEY,
+    mtime INTEGER NOT NULL
+);
+CREATE TABLE review_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    card_id BLOB NOT NULL,
+    reviewed_at TEXT NOT NULL,
+    quality INTEGER NOT NULL,
+    algorithm TEXT NOT NULL,
+    interval_before INTEGER NOT NULL,
+    interval_after INTEGER NOT NULL,
+    ease_before REAL NOT NULL,
+    ease_after REAL NOT NULL,
+    reversed INTEGER NOT NULL,
+    duration_ms INTEGER NOT NULL
+);
+CREATE INDEX idx_review_log_card ON review_log (card_id, reviewed_at);
+CREATE INDEX idx_cards_file ON cards (file);
+CREATE INDEX idx_cards_due ON cards (orphan, leech, suspended, due_at);
+CREATE TABLE card_tags (
+    tag TEXT NOT NULL,
+    card_id BLOB NOT NULL,
+    PRIMARY KEY (tag, card_id)
+) WITHOUT ROWID;
+CREATE INDEX idx_card_tags_card ON card_tags (card_id);
+CREATE TRIGGER card_tags_insert AFTER INSERT ON cards BEGIN
+    DELETE FROM card_tags WHERE card_id = NEW.id;
+    INSERT OR IGNORE INTO card_tags (tag, card_id)
+        SELECT value, NEW.id FROM json_each(NEW.tags);
+END;
+CREATE TRIGGER card_tags_update AFTER UPDATE OF id, tags ON cards BEGIN
+    DELETE FROM card_tags WHERE card_id = OLD.id;
+    INSERT OR IGNORE INTO card_tags (tag, card_id)
+        SELECT value, NEW.id FROM json_each(NEW.tags);
+END;
+CREATE TRIGGER card_tags_delete AFTER DELETE ON cards BEGIN
+    DELETE FROM card_tags WHERE card_id = OLD.id;
+END;
+INSERT INTO cards (id, file, line, prompt, response, tags, added, last_revised, revise_count, leech, orphan, ease_factor, interval, repetitions, failed_count, kind, breadcrumb, hints, hints_used, directives, due_at, priority, suspended)
+VALUES
+    (X'1111111111111111111111111111111111111111111111111111111111111111', 'notes/geo.md', 3, 'Capital of France?', '["Paris"]', '["geo"]', '2024-01-01T00:00:00+00:00', '2024-03-01T12:00:00+00:00', 7, 0, 0, 2.36, 21, 6, 1, '{"type":"basic"}', '["Geography"]', '["On the Seine"]', 2, '{"suspended":false,"priority":"high","max_interval":null}', 1711108800, 2, 0),
+    (X'2222222222222222222222222222222222222222222222222222222222222222', 'notes/old.md', 1, 'Gone card', '["answer"]', '[]', '2024-01-01T00:00:00+00:00', NULL, 0, 1, 1, 2.5, 0, 0, 4, '{"type":"basic"}', '[]', '[]', 0, '{}', 0, 1, 0);
+INSERT INTO global_state (id, optimal_factor_matrix, last_revise_session, mean_q, total_cards_revised)
+VALUES (1, '{}', '2024-03-01T12:00:00+00:00', 3.5, 12);
+INSERT INTO scan_index (file_path, mtime) VALUES ('notes/geo.md', 1709294400);
+INSERT INTO review_log (card_id, reviewed_at, quality, algorithm, interval_before, interval_after, ease_before, ease_after, reversed, duration_ms)
+VALUES (X'1111111111111111111111111111111111111111111111111111111111111111', '2024-03-01T12:00:00+00:00', 4, 'sm5', 8, 21, 2.5, 2.36, 0, 5300);
+PRAGMA user_version = 8;
@@ -163,6 +163,31 @@ fn test_revise_with_tag_filter() {
     assert!(stderr.contains("No cards due"), "stderr: {stderr}");
 }
 
+#[test]
+fn test_revise_tag_filter_uses_front_matter_tags() {
+    let vault = setup_vault("tests/fixtures");
+    let vault_path = vault.path().to_string_lossy().to_string();
+    std::fs::write(
+        vault.path().join("rust.md"),
+        "---\ntags: [rust]\n---\nWhat is Send? : Movable across threads 🧠\n",
+    )
+    .unwrap();
+
+    carddown()
+        .args(["--vault", &vault_path])
+        .args(["scan", &vault_path])
+        .output()
+        .unwrap();
+
+    // A due card is found, so revise gets as far as needing a terminal
+    let output = carddown_no_tty()
+        .args(["--vault", &vault_path])
+        .args(["revise", "-t", "rust"])
+        .output()
+        .unwrap();
+    assert_failure_contains(output, "revise disabled by --no-tty.");
+}
+
 #[test]
 fn test_revise_uses_config_defaults() {
     let vault = setup_vault("tests/fixtures");
@@ -558,6 +583,98 @@ fn test_cli_file_types_override_config() {
     assert!(stderr.contains("Found 4 card(s)"), "stderr: {stderr}");
 }
 
+#[test]
+fn test_scan_parsers_from_config() {
+    let vault = setup_vault("tests/fixtures");
+    let vault_path = vault.path().to_string_lossy().to_string();
+    std::fs::write(
+        vault.path().join("extra.mdx"),
+        "Mdx card : Answer 🧠 #mdx\n",
+    )
+    .unwrap();
+    write_config(
+        &vault,
+        r#"
+        [scan.parsers]
+        mdx = "markdown"
+    "#,
+    );
+
+    let output = carddown()
+        .args(["--vault", &vault_path])
+        .args(["scan", &vault_path])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Found 5 card(s)"), "stderr: {stderr}");
+}
+
+#[test]
+fn test_scan_custom_syntax_from_config() {
+    let vault = setup_vault("tests/fixtures");
+    let vault_path = vault.path().to_string_lossy().to_string();
+    std::fs::write(
+        vault.path().join("custom.md"),
+        "Standup at 10:30 : daily ❓\nRust book :: https://doc.rust-lang.org/book ❓\n",
+    )
+    .unwrap();
+    write_config(
+        &vault,
+        r#"
+        [scan]
+        markers = ["❓"]
+        separator = "::"
+    "#,
+    );
+
+    let output = carddown()
+        .args(["--vault", &vault_path])
+        .args(["scan", &vault_path])
+        .output()
+        .unwrap();
+
+    // The fixtures' `prompt : response` cards no longer split on `:`
+    assert_success_contains(output, "Found 3 card(s)");
+}
+
+#[test]
+fn test_scan_rejects_invalid_syntax_config() {
+    let vault = setup_vault("tests/fixtures");
+    let vault_path = vault.path().to_string_lossy().to_string();
+    write_config(
+        &vault,
+        r#"
+        [scan]
+        separator = " "
+    "#,
+    );
+
+    let output = carddown()
+        .args(["--vault", &vault_path])
+        .args(["scan", &vault_path])
+        .output()
+        .unwrap();
+
+    assert_failure_contains(output, "scan.separator");
+}
+
+#[test]
+fn test_scan_honours_carddownignore() {
+    let vault = setup_vault("tests/fixtures");
+    let vault_path = vault.path().to_string_lossy().to_string();
+    std::fs::write(vault.path().join(".carddownignore"), "multi_*.md\n").unwrap();
+
+    let output = carddown()
+        .args(["--vault", &vault_path])
+        .args(["scan", "--full", &vault_path])
+        .output()
+        .unwrap();
+
+    assert_success_contains(output, "Found 2 card(s)");
+}
+
 #[test]
 fn test_import_dry_run_does_not_write() {
     let vault = setup_vault("tests/fixtures");
@@ -633,12 +750,49 @@ fn test_export() {
 
     assert!(export_dir.join("cards.json").exists());
     assert!(export_dir.join("state.json").exists());
+    assert!(export_dir.join("review_log.json").exists());
 
     let content = std::fs::read_to_string(export_dir.join("cards.json")).unwrap();
     let cards: Vec<serde_json::Value> = serde_json::from_str(&content).unwrap();
     assert_eq!(cards.len(), 4);
 }
 
+#[test]
+fn test_scan_table_cards() {
+    let vault = setup_vault("tests/table_fixtures");
+    let vault_path = vault.path().to_string_lossy().to_string();
+
+    carddown()
+        .args(["--vault", &vault_path])
+        .args(["scan", &vault_path])
+        .output()
+        .unwrap();
+    let export_dir = vault.path().join("export");
+    carddown()
+        .args(["--vault", &vault_path])
+        .args(["export", &export_dir.to_string_lossy()])
+        .output()
+        .unwrap();
+
+    let content = std::fs::read_to_string(export_dir.join("cards.json")).unwrap();
+    let cards: Vec<serde_json::Value> = serde_json::from_str(&content).unwrap();
+    let response = |prompt: &str| {
+        cards
+            .iter()
+            .find(|c| c["card"]["prompt"] == prompt)
+            .map(|c| c["card"]["response"].clone())
+            .unwrap_or_else(|| panic!("no card for {prompt}"))
+    };
+    assert_eq!(
+        response("Sodium"),
+        serde_json::json!(["Symbol: Na", "Atomic number: 11"])
+    );
+    assert_eq!(
+        response("`a | b`"),
+        serde_json::json!(["Meaning: Pipe the output of `a` into `b`"])
+    );
+}
+
 #[test]
 fn test_auto_migrate_from_json() {
     let vault = setup_vault("tests/fixtures");
@@ -700,3 +854,89 @@ fn test_auto_migrate_from_json() {
     let cards: Vec<serde_json::Value> = serde_json::from_str(&content).unwrap();
     assert_eq!(cards.len(), 4);
 }
+
+#[test]
+fn test_scan_assign_ids_keeps_history_across_edits() {
+    let vault = setup_vault("tests/fixtures");
+    let vault_path = vault.path().to_string_lossy().to_string();
+
+    let output = carddown()
+        .args(["--vault", &vault_path])
+        .args(["scan", "--full", "--assign-ids", &vault_path])
+        .output()
+        .unwrap();
+    assert_success_contains(output, "Assigned ids to 4 card(s)");
+
+    let file = vault.path().join("single_line.md");
+    let content = std::fs::read_to_string(&file).unwrap();
+    assert!(content.contains("^cd-"), "content: {content}");
+    std::fs::write(&file, content.replace("Paris", "Paris, on the Seine")).unwrap();
+
+    let output = carddown()
+        .args(["--vault", &vault_path])
+        .args(["scan", "--full", &vault_path])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("1 updated"), "stderr: {stderr}");
+    assert!(!stderr.contains("new"), "stderr: {stderr}");
+    assert!(!stderr.contains("orphaned"), "stderr: {stderr}");
+}
+
+#[test]
+fn test_scan_assign_ids_unique_across_runs() {
+    let vault = setup_vault("tests/fixtures");
+    let vault_path = vault.path().to_string_lossy().to_string();
+    for name in ["a.md", "b.md"] {
+        let file = vault.path().join(name);
+        std::fs::write(&file, "Same question : same answer 🧠\n").unwrap();
+        let output = carddown()
+            .args(["--vault", &vault_path])
+            .args(["scan", "--assign-ids", &file.to_string_lossy()])
+            .output()
+            .unwrap();
+        assert_success_contains(output, "Assigned ids to 1 card(s)");
+    }
+
+    let anchor = |name: &str| {
+        let content = std::fs::read_to_string(vault.path().join(name)).unwrap();
+        content.split("^cd-").nth(1).unwrap().trim().to_string()
+    };
+    assert_ne!(anchor("a.md"), anchor("b.md"));
+}
+
+#[test]
+fn test_scan_dry_run_lists_migrated_cards() {
+    let vault = setup_vault("tests/fixtures");
+    let vault_path = vault.path().to_string_lossy().to_string();
+
+    carddown()
+        .args(["--vault", &vault_path])
+        .args(["scan", &vault_path])
+        .output()
+        .unwrap();
+
+    let file = vault.path().join("single_line.md");
+    let content = std::fs::read_to_string(&file).unwrap();
+    std::fs::write(
+        &file,
+        content.replace("Capital of France?", "Capital of France ?"),
+    )
+    .unwrap();
+
+    let output = carddown()
+        .args(["--vault", &vault_path])
+        .args(["scan", "--full", "--dry-run", &vault_path])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("1 migrated"), "stderr: {stderr}");
+    assert!(
+        stderr.contains(r#""Capital of France?" -> "#)
+            && stderr.contains(r#""Capital of France ?""#),
+        "stderr: {stderr}"
+    );
+    assert!(!stderr.contains("orphaned"), "stderr: {stderr}");
+}